@@ -0,0 +1,123 @@
+use num::{complex::Complex, Float};
+
+use crate::{error::QubError, qubit::Qubit, qugate::QuGate};
+
+/// A single-qubit measurement basis, made of two orthonormal states.
+/// Measuring in a basis yields `false` for its first state and `true` for its second.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Basis<T: Float> {
+    /// The computational basis {|0⟩, |1⟩}, the eigenstates of Pauli-Z.
+    Z,
+    /// The Hadamard basis {|+⟩, |−⟩}, the eigenstates of Pauli-X.
+    X,
+    /// The circular basis {|+i⟩, |−i⟩}, the eigenstates of Pauli-Y.
+    Y,
+    /// A custom basis, whose two states are expected to be orthonormal.
+    /// Use `Basis::try_custom` to check that they are.
+    Custom(Qubit<T>, Qubit<T>),
+}
+
+impl<T: Float> Basis<T> {
+    /// Create a custom basis from two states,
+    /// failing if they are not orthonormal within the given tolerance.
+    pub fn try_custom(zero: Qubit<T>, one: Qubit<T>, tolerance: T) -> Result<Self, QubError> {
+        let basis = Self::Custom(zero, one);
+        if !basis.is_orthonormal(tolerance) {
+            return Err(QubError::NotOrthonormal);
+        }
+
+        Ok(basis)
+    }
+
+    /// Check whether the two states of the basis have unit norm and are orthogonal,
+    /// i.e. ⟨a|b⟩ = 0, within the given tolerance.
+    pub fn is_orthonormal(&self, tolerance: T) -> bool {
+        let (zero, one) = self.states();
+        zero.validate_with(tolerance)
+            && one.validate_with(tolerance)
+            && zero.inner(&one).norm() <= tolerance
+    }
+
+    /// Get the two states of the basis.
+    pub fn states(&self) -> (Qubit<T>, Qubit<T>) {
+        let norm_factor = T::one() / (T::one() + T::one()).sqrt();
+        let real = |x: T| Complex::new(x, T::zero());
+
+        match self {
+            Self::Z => (Qubit::zero(), Qubit::one()),
+            Self::X => (
+                Qubit::new(real(norm_factor), real(norm_factor)),
+                Qubit::new(real(norm_factor), real(-norm_factor)),
+            ),
+            Self::Y => (
+                Qubit::new(real(norm_factor), Complex::new(T::zero(), norm_factor)),
+                Qubit::new(real(norm_factor), Complex::new(T::zero(), -norm_factor)),
+            ),
+            Self::Custom(zero, one) => (zero.clone(), one.clone()),
+        }
+    }
+}
+
+impl<T: Float + 'static> Basis<T> {
+    /// Create the basis {U|0⟩, U|1⟩} reached from the computational basis
+    /// by a single-qubit basis change gate U.
+    ///
+    /// # Panics
+    /// Panics if the gate is not a single-qubit gate.
+    pub fn from_gate(gate: &QuGate<T>) -> Self {
+        Self::Custom(gate.apply(&Qubit::zero()), gate.apply(&Qubit::one()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states() {
+        let (plus, minus) = Basis::<f64>::X.states();
+        let h_gate = QuGate::hadamard();
+
+        assert_eq!(Basis::from_gate(&h_gate), Basis::Custom(plus, minus));
+        assert_eq!(
+            Basis::from_gate(&QuGate::<f64>::identity(1)).states(),
+            Basis::Z.states()
+        );
+    }
+
+    #[test]
+    fn orthonormal() {
+        for basis in [Basis::<f64>::Z, Basis::X, Basis::Y] {
+            let (zero, one) = basis.states();
+            assert!(zero.validate_with(1e-12) && one.validate_with(1e-12));
+            assert!(zero.inner(&one).norm() < 1e-12);
+            assert!(basis.is_orthonormal(1e-12));
+        }
+    }
+
+    #[test]
+    fn try_custom() {
+        let (plus, minus) = Basis::<f64>::X.states();
+
+        assert_eq!(
+            Basis::try_custom(plus.clone(), minus.clone(), 1e-12),
+            Ok(Basis::Custom(plus.clone(), minus))
+        );
+        assert_eq!(
+            Basis::try_custom(Qubit::zero(), Qubit::zero(), 1e-12),
+            Err(QubError::NotOrthonormal)
+        );
+        assert_eq!(
+            Basis::try_custom(Qubit::zero(), plus, 1e-12),
+            Err(QubError::NotOrthonormal)
+        );
+        assert_eq!(
+            Basis::try_custom(
+                Qubit::zero(),
+                Qubit::new(Complex::new(0.0, 0.0), Complex::new(2.0, 0.0)),
+                1e-12
+            ),
+            Err(QubError::NotOrthonormal)
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use ndarray::prelude::*;
+use num::{complex::Complex, Float};
+use rand::prelude::*;
+
+use crate::{
+    density_matrix::DensityMatrix,
+    error::QubError,
+    qubit::Qubit,
+    qugate::QuGate,
+    quregister::{qubit_masks, sample_index, QuRegister},
+};
+
+/// A quantum channel is a completely positive, trace preserving map
+/// that can describe noise and other non-unitary evolution.
+/// It is defined by its Kraus operators Kᵢ, acting on a state as ρ → Σ Kᵢ ρ Kᵢ†.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel<T: Float> {
+    /// The Kraus operators of the channel, which need not be unitary.
+    kraus: Vec<QuGate<T>>,
+}
+
+impl<T: Float + 'static> Channel<T> {
+    /// Create a new channel from the given Kraus operators.
+    ///
+    /// # Panics
+    /// Panics if there are no operators, or if they are not all square
+    /// with the same power of two size.
+    pub fn new(kraus: Vec<Array2<Complex<T>>>) -> Self {
+        Self::try_new(kraus).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Create a new channel from the given Kraus operators,
+    /// failing if there are no operators, or if they are not all square
+    /// with the same power of two size.
+    pub fn try_new(kraus: Vec<Array2<Complex<T>>>) -> Result<Self, QubError> {
+        let kraus: Vec<QuGate<T>> = kraus.into_iter().map(QuGate::new).collect();
+        let n_qubits = kraus.first().ok_or(QubError::EmptyChannel)?.n_qubits();
+        for k in &kraus {
+            k.check_size(n_qubits)?;
+        }
+
+        Ok(Self { kraus })
+    }
+
+    /// Get the Kraus operators of the channel.
+    pub fn kraus(&self) -> impl Iterator<Item = &Array2<Complex<T>>> {
+        self.kraus.iter().map(QuGate::matrix)
+    }
+
+    /// Get the number of qubits the channel acts on.
+    pub fn n_qubits(&self) -> usize {
+        self.kraus[0].n_qubits()
+    }
+
+    /// Check whether the channel preserves the trace, i.e. Σ Kᵢ†Kᵢ = I,
+    /// within the given tolerance.
+    pub fn is_trace_preserving(&self, tolerance: T) -> bool {
+        let dim = 1 << self.n_qubits();
+        let sum = self
+            .kraus
+            .iter()
+            .fold(Array2::zeros((dim, dim)), |acc: Array2<Complex<T>>, k| {
+                acc + k.dagger().matrix().dot(k.matrix())
+            });
+
+        (sum - Array2::<Complex<T>>::eye(dim))
+            .iter()
+            .all(|x| x.norm() <= tolerance)
+    }
+
+    /// Apply the channel to the given qubits of a density matrix, as Σ Kᵢ ρ Kᵢ†.
+    ///
+    /// # Panics
+    /// Panics if the number of qubits does not match the channel,
+    /// or if a qubit is out of range or repeated.
+    pub fn apply(&self, rho: &DensityMatrix<T>, qubits: &[usize]) -> DensityMatrix<T> {
+        self.try_apply(rho, qubits)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Apply the channel to the given qubits of a density matrix, as Σ Kᵢ ρ Kᵢ†,
+    /// failing if the number of qubits does not match the channel,
+    /// or if a qubit is out of range or repeated.
+    pub fn try_apply(
+        &self,
+        rho: &DensityMatrix<T>,
+        qubits: &[usize],
+    ) -> Result<DensityMatrix<T>, QubError> {
+        self.kraus[0].check_size(qubits.len())?;
+        qubit_masks(qubits, rho.n_qubits())?;
+        let matrix = self
+            .kraus
+            .iter()
+            .map(|k| rho.evolve_qubits(k, qubits).matrix)
+            .reduce(|acc, matrix| acc + matrix)
+            .ok_or(QubError::EmptyChannel)?;
+
+        Ok(DensityMatrix { matrix })
+    }
+
+    /// Apply the channel stochastically to the given qubits of a register.
+    /// A single Kraus operator Kᵢ is picked with probability ‖Kᵢ|ψ⟩‖²
+    /// and the resulting state is renormalised,
+    /// so that averaging over many runs reproduces the channel.
+    ///
+    /// # Panics
+    /// Panics if the number of qubits does not match the channel,
+    /// or if a qubit is out of range or repeated.
+    pub fn apply_register(&self, register: &QuRegister<T>, qubits: &[usize]) -> QuRegister<T> {
+        self.apply_register_with(register, qubits, &mut thread_rng())
+    }
+
+    /// Apply the channel stochastically to the given qubits of a register,
+    /// drawing randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if the number of qubits does not match the channel,
+    /// or if a qubit is out of range or repeated.
+    pub fn apply_register_with<R: Rng + ?Sized>(
+        &self,
+        register: &QuRegister<T>,
+        qubits: &[usize],
+        rng: &mut R,
+    ) -> QuRegister<T> {
+        self.try_apply_register_with(register, qubits, rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Apply the channel stochastically to the given qubits of a register,
+    /// drawing randomness from the given random number generator,
+    /// failing if the number of qubits does not match the channel,
+    /// if a qubit is out of range or repeated, or if the register has zero norm.
+    pub fn try_apply_register_with<R: Rng + ?Sized>(
+        &self,
+        register: &QuRegister<T>,
+        qubits: &[usize],
+        rng: &mut R,
+    ) -> Result<QuRegister<T>, QubError> {
+        let branches = self
+            .kraus
+            .iter()
+            .map(|k| k.try_apply_to_qubits(register, qubits))
+            .collect::<Result<Vec<_>, _>>()?;
+        let probabilities: Vec<T> = branches
+            .iter()
+            .map(|branch| branch.probabilities().sum())
+            .collect();
+
+        let index = sample_index(&probabilities, rng)?;
+        let norm = probabilities[index].sqrt();
+
+        Ok(QuRegister {
+            state: branches[index].state.mapv(|x| x / norm),
+        })
+    }
+
+    /// Apply a single-qubit channel stochastically to a qubit.
+    ///
+    /// # Panics
+    /// Panics if the channel does not act on a single qubit.
+    pub fn apply_qubit(&self, qubit: &Qubit<T>) -> Qubit<T> {
+        self.apply_qubit_with(qubit, &mut thread_rng())
+    }
+
+    /// Apply a single-qubit channel stochastically to a qubit,
+    /// drawing randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if the channel does not act on a single qubit.
+    pub fn apply_qubit_with<R: Rng + ?Sized>(&self, qubit: &Qubit<T>, rng: &mut R) -> Qubit<T> {
+        self.try_apply_qubit_with(qubit, rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Apply a single-qubit channel stochastically to a qubit,
+    /// drawing randomness from the given random number generator,
+    /// failing if the channel does not act on a single qubit
+    /// or if the qubit has zero norm.
+    pub fn try_apply_qubit_with<R: Rng + ?Sized>(
+        &self,
+        qubit: &Qubit<T>,
+        rng: &mut R,
+    ) -> Result<Qubit<T>, QubError> {
+        let register = self.try_apply_register_with(&QuRegister::from(qubit.clone()), &[0], rng)?;
+        Ok(Qubit {
+            state: register.state,
+        })
+    }
+}
+
+impl<T: Float + 'static> Channel<T> {
+    /// Create a depolarizing channel, which replaces the state of a qubit
+    /// with the maximally mixed state with probability p.
+    pub fn depolarizing(p: T) -> Self {
+        let two = T::one() + T::one();
+        let quarter = p / (two * two);
+        let identity_weight = (T::one() - (two + T::one()) * quarter).sqrt();
+        let pauli_weight = quarter.sqrt();
+
+        Self::new(vec![
+            QuGate::identity(1).matrix().mapv(|x| x * identity_weight),
+            QuGate::pauli_x().matrix().mapv(|x| x * pauli_weight),
+            QuGate::pauli_y().matrix().mapv(|x| x * pauli_weight),
+            QuGate::pauli_z().matrix().mapv(|x| x * pauli_weight),
+        ])
+    }
+
+    /// Create a bit-flip channel, which applies a Pauli-X gate with probability p.
+    pub fn bit_flip(p: T) -> Self {
+        Self::pauli_error(QuGate::pauli_x(), p)
+    }
+
+    /// Create a phase-flip channel, which applies a Pauli-Z gate with probability p.
+    pub fn phase_flip(p: T) -> Self {
+        Self::pauli_error(QuGate::pauli_z(), p)
+    }
+
+    fn pauli_error(pauli: QuGate<T>, p: T) -> Self {
+        Self::new(vec![
+            QuGate::identity(1)
+                .matrix()
+                .mapv(|x| x * (T::one() - p).sqrt()),
+            pauli.matrix().mapv(|x| x * p.sqrt()),
+        ])
+    }
+
+    /// Create an amplitude-damping channel, which decays |1⟩ to |0⟩
+    /// with probability gamma, modelling energy relaxation.
+    pub fn amplitude_damping(gamma: T) -> Self {
+        let zero = Complex::new(T::zero(), T::zero());
+        let one = Complex::new(T::one(), T::zero());
+
+        Self::new(vec![
+            array![
+                [one, zero],
+                [zero, Complex::new((T::one() - gamma).sqrt(), T::zero())]
+            ],
+            array![[zero, Complex::new(gamma.sqrt(), T::zero())], [zero, zero]],
+        ])
+    }
+
+    /// Create a phase-damping channel, which loses phase information
+    /// between |0⟩ and |1⟩ with probability lambda, modelling dephasing.
+    pub fn phase_damping(lambda: T) -> Self {
+        let zero = Complex::new(T::zero(), T::zero());
+        let one = Complex::new(T::one(), T::zero());
+
+        Self::new(vec![
+            array![
+                [one, zero],
+                [zero, Complex::new((T::one() - lambda).sqrt(), T::zero())]
+            ],
+            array![[zero, zero], [zero, Complex::new(lambda.sqrt(), T::zero())]],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_preserving() {
+        for channel in [
+            Channel::<f64>::depolarizing(0.3),
+            Channel::bit_flip(0.1),
+            Channel::phase_flip(0.2),
+            Channel::amplitude_damping(0.4),
+            Channel::phase_damping(0.5),
+        ] {
+            assert!(channel.is_trace_preserving(1e-12));
+        }
+    }
+
+    #[test]
+    fn depolarizing() {
+        let rho = DensityMatrix::from(Qubit::<f64>::zero());
+        let mixed = Channel::depolarizing(1.0).apply(&rho, &[0]);
+
+        assert!((mixed.purity() - 0.5).abs() < 1e-12);
+        assert!((mixed.probabilities()[0] - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn amplitude_damping() {
+        let rho = DensityMatrix::from(QuRegister::<f64>::basis(2, 0b01));
+        let channel = Channel::amplitude_damping(1.0);
+
+        assert_eq!(
+            channel.apply(&rho, &[1]),
+            DensityMatrix::from(QuRegister::zero(2))
+        );
+        assert_eq!(channel.apply_qubit(&Qubit::one()), Qubit::zero());
+    }
+
+    #[test]
+    fn bit_flip() {
+        let register = QuRegister::<f64>::zero(2);
+        let flipped = Channel::bit_flip(1.0).apply_register(&register, &[0]);
+
+        assert_eq!(flipped, QuRegister::basis(2, 0b10));
+    }
+
+    #[test]
+    fn errors() {
+        let mut rng = thread_rng();
+        let channel = Channel::<f64>::bit_flip(0.5);
+
+        assert_eq!(
+            Channel::<f64>::try_new(Vec::new()),
+            Err(QubError::EmptyChannel)
+        );
+        assert_eq!(
+            Channel::<f64>::try_new(vec![Array2::eye(2), Array2::eye(4)]),
+            Err(QubError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            Channel::<f64>::try_new(vec![Array2::eye(3)]),
+            Err(QubError::InvalidShape {
+                rows: 3,
+                columns: 3
+            })
+        );
+        assert_eq!(
+            channel.try_apply(&DensityMatrix::zero(1), &[1]),
+            Err(QubError::QubitOutOfRange {
+                qubit: 1,
+                n_qubits: 1
+            })
+        );
+        assert_eq!(
+            channel.try_apply_register_with(&QuRegister::zero(2), &[0, 1], &mut rng),
+            Err(QubError::DimensionMismatch {
+                expected: 1,
+                found: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use ndarray::prelude::*;
+use num::{complex::Complex, Float};
+
+/// Check whether two arrays of amplitudes are element-wise equal within the tolerance.
+pub(crate) fn approx_eq<T: Float, D: Dimension>(
+    a: &Array<Complex<T>, D>,
+    b: &Array<Complex<T>, D>,
+    tolerance: T,
+) -> bool {
+    a.shape() == b.shape() && a.iter().zip(b).all(|(x, y)| (x - y).norm() <= tolerance)
+}
+
+/// Check whether two arrays of amplitudes are element-wise equal within the tolerance
+/// once the global phase e^{iφ} that best aligns them is factored out of `b`.
+pub(crate) fn eq_up_to_global_phase<T: Float, D: Dimension>(
+    a: &Array<Complex<T>, D>,
+    b: &Array<Complex<T>, D>,
+    tolerance: T,
+) -> bool {
+    if a.shape() != b.shape() {
+        return false;
+    }
+
+    let overlap = a
+        .iter()
+        .zip(b)
+        .fold(Complex::new(T::zero(), T::zero()), |acc, (x, y)| {
+            acc + y.conj() * x
+        });
+    let phase = if overlap.norm() > T::zero() {
+        overlap / overlap.norm()
+    } else {
+        Complex::new(T::one(), T::zero())
+    };
+
+    a.iter()
+        .zip(b)
+        .all(|(x, y)| (x - y * phase).norm() <= tolerance)
+}
+
+#[cfg(feature = "approx")]
+pub(crate) fn relative_eq<T: Float + approx::RelativeEq<Epsilon = T>, D: Dimension>(
+    a: &Array<Complex<T>, D>,
+    b: &Array<Complex<T>, D>,
+    epsilon: T,
+    max_relative: T,
+) -> bool {
+    a.shape() == b.shape()
+        && a.iter().zip(b).all(|(x, y)| {
+            x.re.relative_eq(&y.re, epsilon, max_relative)
+                && x.im.relative_eq(&y.im, epsilon, max_relative)
+        })
+}
@@ -0,0 +1,124 @@
+use std::collections::{btree_map, BTreeMap};
+
+use num::Float;
+use rand::{distributions::WeightedIndex, prelude::*};
+
+use crate::error::QubError;
+
+/// A histogram of measurement outcomes over a number of shots.
+/// Outcomes are bitstrings, with qubit 0 as the leftmost bit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Counts {
+    counts: BTreeMap<String, usize>,
+}
+
+impl Counts {
+    /// Create a new empty histogram.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sample the given number of shots of an n-qubit measurement
+    /// from the probabilities of each basis state,
+    /// failing if they are all zero.
+    pub(crate) fn sample<T: Float, R: Rng + ?Sized>(
+        n_qubits: usize,
+        probabilities: impl IntoIterator<Item = T>,
+        shots: usize,
+        rng: &mut R,
+    ) -> Result<Self, QubError> {
+        let weights = probabilities
+            .into_iter()
+            .map(|p| p.to_f64().ok_or(QubError::NumericConversion))
+            .collect::<Result<Vec<_>, _>>()?;
+        let distribution = WeightedIndex::new(&weights).map_err(|_| QubError::ZeroNorm)?;
+
+        let mut hits = vec![0; weights.len()];
+        for _ in 0..shots {
+            hits[distribution.sample(rng)] += 1;
+        }
+
+        let mut counts = Self::new();
+        for (index, &hits) in hits.iter().enumerate().filter(|(_, &hits)| hits > 0) {
+            counts.add(format!("{index:0n_qubits$b}"), hits);
+        }
+        Ok(counts)
+    }
+
+    /// Record the given number of hits of an outcome.
+    pub fn add(&mut self, outcome: impl Into<String>, hits: usize) {
+        *self.counts.entry(outcome.into()).or_insert(0) += hits;
+    }
+
+    /// Get the number of hits of an outcome.
+    pub fn get(&self, outcome: &str) -> usize {
+        self.counts.get(outcome).copied().unwrap_or(0)
+    }
+
+    /// Get the fraction of shots that produced an outcome.
+    pub fn frequency(&self, outcome: &str) -> f64 {
+        self.get(outcome) as f64 / self.shots() as f64
+    }
+
+    /// Get the total number of shots recorded.
+    pub fn shots(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Get the number of distinct outcomes recorded.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Check whether no outcome has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Iterate over the outcomes and their hits, in bitstring order.
+    pub fn iter(&self) -> btree_map::Iter<'_, String, usize> {
+        self.counts.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Counts {
+    type Item = (&'a String, &'a usize);
+    type IntoIter = btree_map::Iter<'a, String, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let counts = Counts::sample(2, [0.5, 0.0, 0.0, 0.5], 1000, &mut rng).unwrap();
+
+        assert_eq!(counts.shots(), 1000);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts.get("01"), 0);
+        assert!((counts.frequency("00") - 0.5).abs() < 0.1);
+        assert_eq!(
+            Counts::sample(1, [0.0, 0.0], 1, &mut rng),
+            Err(QubError::ZeroNorm)
+        );
+    }
+
+    #[test]
+    fn add() {
+        let mut counts = Counts::new();
+        counts.add("1", 3);
+        counts.add("0", 1);
+        counts.add("1", 2);
+
+        assert_eq!(
+            counts.iter().collect::<Vec<_>>(),
+            [(&"0".to_string(), &1), (&"1".to_string(), &5)]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use ndarray::prelude::*;
+use num::{complex::Complex, Float};
+use rand::prelude::*;
+
+use crate::{
+    error::QubError,
+    qubit::Qubit,
+    qugate::QuGate,
+    quregister::{qubit_masks, sample_index, QuRegister},
+};
+
+/// A density matrix describes the state of a quantum system
+/// that may be in a statistical mixture of pure states.
+/// For n qubits, it is a 2^n x 2^n positive semi-definite complex matrix
+/// with unit trace, using the same basis ordering as `QuRegister`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityMatrix<T: Float> {
+    /// The density matrix, of size 2^n x 2^n.
+    pub(crate) matrix: Array2<Complex<T>>,
+}
+
+impl<T: Float + 'static> DensityMatrix<T> {
+    /// Create a new density matrix from the given matrix.
+    ///
+    /// # Panics
+    /// Panics if the matrix is not square with a power of two size.
+    pub fn new(matrix: Array2<Complex<T>>) -> Self {
+        Self::try_new(matrix).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Create a new density matrix from the given matrix,
+    /// failing if the matrix is not square with a power of two size.
+    pub fn try_new(matrix: Array2<Complex<T>>) -> Result<Self, QubError> {
+        let (rows, columns) = matrix.dim();
+        if rows != columns || !rows.is_power_of_two() {
+            return Err(QubError::InvalidShape { rows, columns });
+        }
+
+        Ok(Self { matrix })
+    }
+
+    /// Create a density matrix of n qubits in the |0...0⟩ state.
+    pub fn zero(n_qubits: usize) -> Self {
+        Self::from_register(&QuRegister::zero(n_qubits))
+    }
+
+    /// Create the density matrix |ψ⟩⟨ψ| of a pure qubit state.
+    pub fn from_qubit(qubit: &Qubit<T>) -> Self {
+        Self::from_state(&qubit.state)
+    }
+
+    /// Create the density matrix |ψ⟩⟨ψ| of a pure register state.
+    pub fn from_register(register: &QuRegister<T>) -> Self {
+        Self::from_state(&register.state)
+    }
+
+    fn from_state(state: &Array1<Complex<T>>) -> Self {
+        let dim = state.len();
+        Self {
+            matrix: Array2::from_shape_fn((dim, dim), |(row, column)| {
+                state[row] * state[column].conj()
+            }),
+        }
+    }
+
+    /// Create the statistical mixture Σ pᵢ ρᵢ of the given weighted states.
+    ///
+    /// # Panics
+    /// Panics if there are no states or if they do not all have the same size.
+    pub fn mixture(states: impl IntoIterator<Item = (T, Self)>) -> Self {
+        states
+            .into_iter()
+            .map(|(weight, state)| state.matrix.mapv(|x| x * weight))
+            .reduce(|acc, matrix| acc + matrix)
+            .map(Self::new)
+            .expect("mixture must contain at least one state")
+    }
+
+    /// Get the density matrix.
+    pub fn matrix(&self) -> &Array2<Complex<T>> {
+        &self.matrix
+    }
+
+    /// Get the number of qubits described by the density matrix.
+    pub fn n_qubits(&self) -> usize {
+        self.matrix.nrows().trailing_zeros() as usize
+    }
+
+    /// Get the trace of the density matrix.
+    pub fn trace(&self) -> T {
+        self.matrix
+            .diag()
+            .iter()
+            .fold(T::zero(), |acc, x| acc + x.re)
+    }
+
+    /// Get the purity Tr(ρ²) of the state,
+    /// which is 1 for pure states and 1/2^n for the maximally mixed state.
+    pub fn purity(&self) -> T {
+        self.matrix
+            .iter()
+            .fold(T::zero(), |acc, x| acc + x.norm_sqr())
+    }
+
+    /// Get the probabilities of the system being in each basis state.
+    pub fn probabilities(&self) -> Array1<T> {
+        self.matrix.diag().mapv(|x| x.re)
+    }
+
+    /// Validate the density matrix state.
+    pub fn validate(&self) -> bool {
+        self.trace() == T::one()
+    }
+
+    /// Evolve the state by a gate acting on every qubit, as UρU†.
+    pub fn evolve(&self, gate: &QuGate<T>) -> Self {
+        Self {
+            matrix: gate.matrix().dot(&self.matrix).dot(gate.dagger().matrix()),
+        }
+    }
+
+    /// Evolve the state by a gate acting on the given qubits, as UρU†.
+    /// This avoids building the full 2^n x 2^n operator.
+    ///
+    /// # Panics
+    /// Panics if the number of qubits does not match the gate,
+    /// or if a qubit is out of range or repeated.
+    pub fn evolve_qubits(&self, gate: &QuGate<T>, qubits: &[usize]) -> Self {
+        // UρU† = (U (Uρ)†)†, applying U to the columns of a matrix each time.
+        let left = |matrix: &Array2<Complex<T>>| {
+            let mut result = matrix.clone();
+            for mut column in result.columns_mut() {
+                let register = QuRegister::new(column.to_owned());
+                column.assign(&gate.apply_to_qubits(&register, qubits).state);
+            }
+            result
+        };
+        let adjoint = |matrix: Array2<Complex<T>>| matrix.t().mapv(|x| x.conj());
+
+        Self {
+            matrix: adjoint(left(&adjoint(left(&self.matrix)))),
+        }
+    }
+
+    /// Measure every qubit in the computational basis.
+    /// Collapse the state to one of its basis states.
+    pub fn measure(&self) -> Self {
+        self.measure_with(&mut thread_rng())
+    }
+
+    /// Measure every qubit in the computational basis,
+    /// drawing randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if the state has zero trace.
+    pub fn measure_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Self {
+        self.try_measure_with(rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Measure every qubit in the computational basis,
+    /// drawing randomness from the given random number generator,
+    /// failing if the state has zero trace.
+    pub fn try_measure_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<Self, QubError> {
+        let outcome = sample_index(&self.probabilities().to_vec(), rng)?;
+        Ok(Self::from_register(&QuRegister::basis(
+            self.n_qubits(),
+            outcome,
+        )))
+    }
+
+    /// Measure a single qubit in the computational basis.
+    /// Return the observed bit and the state with that qubit collapsed
+    /// and the remaining state renormalised.
+    ///
+    /// # Panics
+    /// Panics if the qubit is out of range.
+    pub fn measure_qubit(&self, qubit: usize) -> (bool, Self) {
+        self.measure_qubit_with(qubit, &mut thread_rng())
+    }
+
+    /// Measure a single qubit in the computational basis,
+    /// drawing randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if the qubit is out of range or if the state has zero trace.
+    pub fn measure_qubit_with<R: Rng + ?Sized>(&self, qubit: usize, rng: &mut R) -> (bool, Self) {
+        self.try_measure_qubit_with(qubit, rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Measure a single qubit in the computational basis,
+    /// drawing randomness from the given random number generator,
+    /// failing if the qubit is out of range or if the state has zero trace.
+    pub fn try_measure_qubit_with<R: Rng + ?Sized>(
+        &self,
+        qubit: usize,
+        rng: &mut R,
+    ) -> Result<(bool, Self), QubError> {
+        let mask = qubit_masks(&[qubit], self.n_qubits())?[0];
+
+        let mut outcome_probabilities = [T::zero(); 2];
+        for (index, &p) in self.probabilities().indexed_iter() {
+            let bit = usize::from(index & mask != 0);
+            outcome_probabilities[bit] = outcome_probabilities[bit] + p;
+        }
+        let outcome = sample_index(&outcome_probabilities, rng)? == 1;
+        let probability = outcome_probabilities[usize::from(outcome)];
+
+        // PρP / p, with P the projector onto the observed outcome.
+        let matrix = Array2::from_shape_fn(self.matrix.raw_dim(), |(row, column)| {
+            if (row & mask != 0) == outcome && (column & mask != 0) == outcome {
+                self.matrix[[row, column]] / probability
+            } else {
+                Complex::new(T::zero(), T::zero())
+            }
+        });
+
+        Ok((outcome, Self { matrix }))
+    }
+}
+
+impl<T: Float + 'static> From<Qubit<T>> for DensityMatrix<T> {
+    fn from(qubit: Qubit<T>) -> Self {
+        Self::from_qubit(&qubit)
+    }
+}
+
+impl<T: Float + 'static> From<QuRegister<T>> for DensityMatrix<T> {
+    fn from(register: QuRegister<T>) -> Self {
+        Self::from_register(&register)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pure() {
+        let rho = DensityMatrix::from(Qubit::<f64>::one());
+        assert_eq!(rho.n_qubits(), 1);
+        assert_eq!(rho.purity(), 1.0);
+        assert!(rho.validate());
+        assert_eq!(rho.measure(), rho);
+    }
+
+    #[test]
+    fn mixture() {
+        let rho = DensityMatrix::mixture([
+            (0.5, DensityMatrix::from(Qubit::<f64>::zero())),
+            (0.5, DensityMatrix::from(Qubit::one())),
+        ]);
+
+        assert_eq!(rho.purity(), 0.5);
+        assert!((rho.evolve(&QuGate::hadamard()).purity() - 0.5).abs() < 1e-12);
+        assert_eq!(rho.probabilities(), array![0.5, 0.5]);
+    }
+
+    #[test]
+    fn evolve() {
+        let rho = DensityMatrix::<f64>::zero(3);
+        let x_gate = QuGate::pauli_x();
+
+        assert_eq!(
+            rho.evolve_qubits(&x_gate, &[1]),
+            DensityMatrix::from(QuRegister::basis(3, 0b010))
+        );
+        assert_eq!(
+            rho.evolve_qubits(&QuGate::hadamard(), &[0]),
+            rho.evolve(&QuGate::hadamard().tensor(&QuGate::identity(2)))
+        );
+    }
+
+    #[test]
+    fn measure_qubit() {
+        let bell = QuGate::<f64>::cnot()
+            .apply_register(&QuGate::hadamard().apply_to(&QuRegister::zero(2), 0));
+        let (outcome, rho) = DensityMatrix::from(bell).measure_qubit(0);
+        let index = if outcome { 0b11 } else { 0b00 };
+
+        assert!((rho.probabilities()[index] - 1.0).abs() < 1e-12);
+        assert!((rho.purity() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn errors() {
+        let mut rng = thread_rng();
+
+        assert_eq!(
+            DensityMatrix::<f64>::try_new(Array2::zeros((2, 3))),
+            Err(QubError::InvalidShape {
+                rows: 2,
+                columns: 3
+            })
+        );
+        assert_eq!(
+            DensityMatrix::<f64>::zero(2).try_measure_qubit_with(2, &mut rng),
+            Err(QubError::QubitOutOfRange {
+                qubit: 2,
+                n_qubits: 2
+            })
+        );
+        assert_eq!(
+            DensityMatrix::<f64>::new(Array2::zeros((2, 2))).try_measure_with(&mut rng),
+            Err(QubError::ZeroNorm)
+        );
+    }
+}
@@ -0,0 +1,599 @@
+use std::{f64::consts::PI, fmt};
+
+use num::Float;
+
+use crate::qucircuit::{Operation, QuCircuit};
+
+/// The characters a diagram is drawn with.
+struct Symbols {
+    wire: char,
+    clbit_wire: char,
+    vertical: char,
+    double_vertical: char,
+    /// A single vertical line crossing a qubit and a classical wire.
+    cross: [char; 2],
+    /// A double vertical line crossing a qubit and a classical wire.
+    double_cross: [char; 2],
+    control: char,
+    corners: [char; 4],
+    side: char,
+    pins: [char; 2],
+    /// A single vertical line joining the top and the bottom border of a box.
+    joins: [char; 2],
+    /// A double vertical line joining the top and the bottom border of a box.
+    double_joins: [char; 2],
+    measured: char,
+    /// The marks of a condition on a classical bit being 0 and 1.
+    condition: [char; 2],
+    swap: char,
+    barrier: char,
+    /// The marks of a wire continuing on the previous and the next page.
+    continuation: [char; 2],
+    pi: &'static str,
+}
+
+const UNICODE: Symbols = Symbols {
+    wire: '─',
+    clbit_wire: '═',
+    vertical: '│',
+    double_vertical: '║',
+    cross: ['┼', '╪'],
+    double_cross: ['╫', '╬'],
+    control: '■',
+    corners: ['┌', '┐', '└', '┘'],
+    side: '│',
+    pins: ['┤', '├'],
+    joins: ['┴', '┬'],
+    double_joins: ['╨', '╥'],
+    measured: '╩',
+    condition: ['○', '●'],
+    swap: '╳',
+    barrier: '░',
+    continuation: ['«', '»'],
+    pi: "π",
+};
+
+const ASCII: Symbols = Symbols {
+    wire: '-',
+    clbit_wire: '=',
+    vertical: '|',
+    double_vertical: '|',
+    cross: ['+', '+'],
+    double_cross: ['+', '+'],
+    control: '*',
+    corners: ['+', '+', '+', '+'],
+    side: '|',
+    pins: ['|', '|'],
+    joins: ['+', '+'],
+    double_joins: ['+', '+'],
+    measured: 'v',
+    condition: ['o', '*'],
+    swap: 'x',
+    barrier: '#',
+    continuation: ['<', '>'],
+    pi: "pi",
+};
+
+/// A text diagram of a quantum circuit, with one wire per qubit and classical bit.
+/// Gates are drawn as labelled boxes joined to their control dots,
+/// measurements as `M` boxes joined to the classical bit they write,
+/// and long circuits are wrapped to the given width.
+#[derive(Debug, Clone)]
+pub struct Diagram<'a, T: Float> {
+    circuit: &'a QuCircuit<T>,
+    ascii: bool,
+    width: usize,
+}
+
+impl<'a, T: Float + 'static> Diagram<'a, T> {
+    /// Create a Unicode diagram of a circuit, wrapped to 80 columns.
+    pub fn new(circuit: &'a QuCircuit<T>) -> Self {
+        Self {
+            circuit,
+            ascii: false,
+            width: 80,
+        }
+    }
+
+    /// Draw the diagram with ASCII characters only.
+    pub fn ascii(mut self) -> Self {
+        self.ascii = true;
+        self
+    }
+
+    /// Wrap the diagram to the given number of columns.
+    /// A page always holds at least one layer of operations, even if it is wider.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    fn symbols(&self) -> &'static Symbols {
+        if self.ascii {
+            &ASCII
+        } else {
+            &UNICODE
+        }
+    }
+
+    pub(crate) fn n_wires(&self) -> usize {
+        self.circuit.n_qubits() + self.circuit.n_clbits()
+    }
+
+    /// Get the wires an operation acts on or reads.
+    fn wires(&self, operation: &Operation<T>) -> Vec<usize> {
+        match operation {
+            Operation::Gate {
+                controls, targets, ..
+            } => controls.iter().chain(targets).copied().collect(),
+            Operation::Measure { qubit, clbit } => {
+                vec![*qubit, self.circuit.n_qubits() + clbit]
+            }
+            Operation::Barrier(qubits) => qubits.clone(),
+            Operation::Conditional {
+                clbits, operation, ..
+            } => clbits
+                .iter()
+                .map(|clbit| self.circuit.n_qubits() + clbit)
+                .chain(self.wires(operation))
+                .collect(),
+        }
+    }
+
+    /// Get the first and last wire an operation is drawn across.
+    pub(crate) fn span(&self, operation: &Operation<T>) -> (usize, usize) {
+        let wires = self.wires(operation);
+        let first = wires.iter().copied().min().unwrap_or(0);
+        (first, wires.into_iter().max().unwrap_or(first))
+    }
+
+    /// Group the operations into layers, placing each one as early as possible
+    /// after the operations drawn across any of its wires.
+    pub(crate) fn layers(&self) -> Vec<Vec<&'a Operation<T>>> {
+        let mut next_layer = vec![0; self.n_wires()];
+        let mut layers: Vec<Vec<&Operation<T>>> = Vec::new();
+
+        // Operations on no wire, like a global phase, are not drawn.
+        for operation in self
+            .circuit
+            .operations()
+            .iter()
+            .filter(|operation| !self.wires(operation).is_empty())
+        {
+            let (first, last) = self.span(operation);
+            let layer = next_layer[first..=last].iter().copied().max().unwrap_or(0);
+            if layer == layers.len() {
+                layers.push(Vec::new());
+            }
+            layers[layer].push(operation);
+            next_layer[first..=last].fill(layer + 1);
+        }
+        layers
+    }
+
+    fn format_param(&self, param: T) -> String {
+        let value = param.to_f64().unwrap();
+        for denominator in [1, 2, 3, 4, 6, 8] {
+            let numerator = value / PI * denominator as f64;
+            if numerator.round() != 0.0 && (numerator - numerator.round()).abs() < 1e-9 {
+                let numerator = numerator.round() as i64;
+                let sign = if numerator < 0 { "-" } else { "" };
+                let multiple = match numerator.abs() {
+                    1 => String::new(),
+                    n => n.to_string(),
+                };
+                let fraction = match denominator {
+                    1 => String::new(),
+                    d => format!("/{d}"),
+                };
+                return format!("{sign}{multiple}{}{fraction}", self.symbols().pi);
+            }
+        }
+
+        let text = format!("{value:.3}");
+        let text = text.trim_end_matches('0').trim_end_matches('.');
+        match text {
+            "-0" => "0".to_string(),
+            text => text.to_string(),
+        }
+    }
+
+    /// Get the name and the formatted parameters of the box an operation
+    /// is drawn with, if any.
+    pub(crate) fn label_parts(&self, operation: &Operation<T>) -> Option<(String, Vec<String>)> {
+        match operation {
+            Operation::Gate { name, params, .. } => {
+                if name == "swap" && params.is_empty() {
+                    return None;
+                }
+                let name = match (name.as_str(), self.ascii) {
+                    ("sdg", false) => "S†".to_string(),
+                    ("tdg", false) => "T†".to_string(),
+                    ("sx", false) => "√X".to_string(),
+                    ("sxdg", false) => "√X†".to_string(),
+                    ("sdg", true) => "Sdg".to_string(),
+                    ("tdg", true) => "Tdg".to_string(),
+                    ("sxdg", true) => "SXdg".to_string(),
+                    ("id", _) => "I".to_string(),
+                    (name, _) => name.to_uppercase(),
+                };
+                let params = params
+                    .iter()
+                    .map(|&param| self.format_param(param))
+                    .collect();
+                Some((name, params))
+            }
+            Operation::Measure { .. } => Some(("M".to_string(), Vec::new())),
+            Operation::Barrier(_) => None,
+            Operation::Conditional { operation, .. } => self.label_parts(operation),
+        }
+    }
+
+    /// Get the label of the box an operation is drawn with, if any.
+    fn label(&self, operation: &Operation<T>) -> Option<String> {
+        let (name, params) = self.label_parts(operation)?;
+        match params.is_empty() {
+            true => Some(name),
+            false => Some(format!("{name}({})", params.join(","))),
+        }
+    }
+
+    /// Draw a layer of operations, returning its rows of text.
+    fn draw_layer(&self, layer: &[&Operation<T>]) -> Vec<Vec<char>> {
+        let width = layer
+            .iter()
+            .filter_map(|operation| self.label(operation))
+            .map(|label| label.chars().count() + 6)
+            .fold(3, usize::max);
+
+        let mut canvas = Canvas {
+            symbols: self.symbols(),
+            n_qubits: self.circuit.n_qubits(),
+            center: width / 2,
+            rows: (0..3 * self.n_wires())
+                .map(|row| vec![self.blank(row); width])
+                .collect(),
+        };
+        for operation in layer {
+            self.draw(&mut canvas, operation);
+        }
+        canvas.rows
+    }
+
+    /// Get the character a row is filled with where nothing is drawn.
+    fn blank(&self, row: usize) -> char {
+        match (row % 3, row / 3 < self.circuit.n_qubits()) {
+            (1, true) => self.symbols().wire,
+            (1, false) => self.symbols().clbit_wire,
+            _ => ' ',
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas, operation: &Operation<T>) {
+        let symbols = self.symbols();
+        match operation {
+            Operation::Gate {
+                controls, targets, ..
+            } => {
+                let (first, last) = self.span(operation);
+                canvas.vertical(3 * first + 1, 3 * last + 1, false);
+
+                match self.label(operation) {
+                    // A gate without targets, like a controlled phase, is boxed on its controls.
+                    Some(label) if targets.is_empty() => {
+                        if let (Some(&top), Some(&bottom)) =
+                            (controls.iter().min(), controls.iter().max())
+                        {
+                            canvas.boxed(top, bottom, controls, &label);
+                        }
+                    }
+                    Some(label) => {
+                        let top = targets.iter().copied().min().unwrap();
+                        let bottom = targets.iter().copied().max().unwrap();
+                        canvas.boxed(top, bottom, targets, &label);
+                        if first < top {
+                            canvas.rows[3 * top][canvas.center] = symbols.joins[0];
+                        }
+                        if last > bottom {
+                            canvas.rows[3 * bottom + 2][canvas.center] = symbols.joins[1];
+                        }
+                        for &control in controls {
+                            canvas.rows[3 * control + 1][canvas.center] = symbols.control;
+                        }
+                    }
+                    None => {
+                        for &target in targets {
+                            canvas.rows[3 * target + 1][canvas.center] = symbols.swap;
+                        }
+                        for &control in controls {
+                            canvas.rows[3 * control + 1][canvas.center] = symbols.control;
+                        }
+                    }
+                }
+            }
+            Operation::Measure { qubit, clbit } => {
+                let wire = self.circuit.n_qubits() + clbit;
+                canvas.vertical(3 * qubit + 1, 3 * wire + 1, true);
+                canvas.boxed(*qubit, *qubit, &[*qubit], "M");
+                canvas.rows[3 * qubit + 2][canvas.center] = symbols.double_joins[1];
+                canvas.rows[3 * wire + 1][canvas.center] = symbols.measured;
+            }
+            Operation::Barrier(qubits) => {
+                for &qubit in qubits {
+                    for row in &mut canvas.rows[3 * qubit..3 * qubit + 3] {
+                        row[canvas.center] = symbols.barrier;
+                    }
+                }
+            }
+            Operation::Conditional {
+                clbits,
+                value,
+                operation,
+            } => {
+                let (_, last) = self.span(operation);
+                let lowest = clbits.iter().copied().max().unwrap_or(0);
+                let wire = self.circuit.n_qubits() + lowest;
+                if !clbits.is_empty() && wire > last {
+                    canvas.vertical(3 * last + 1, 3 * wire + 1, true);
+                }
+                self.draw(canvas, operation);
+                if !clbits.is_empty() && wire > last && self.label(operation).is_some() {
+                    canvas.rows[3 * last + 2][canvas.center] = symbols.double_joins[1];
+                }
+
+                for (bit, &clbit) in clbits.iter().enumerate() {
+                    let set = bit < usize::BITS as usize && (value >> bit) & 1 == 1;
+                    let row = 3 * (self.circuit.n_qubits() + clbit) + 1;
+                    canvas.rows[row][canvas.center] = symbols.condition[usize::from(set)];
+                }
+            }
+        }
+    }
+}
+
+/// The rows of text of a layer being drawn.
+struct Canvas {
+    symbols: &'static Symbols,
+    n_qubits: usize,
+    /// The column vertical lines are drawn in.
+    center: usize,
+    rows: Vec<Vec<char>>,
+}
+
+impl Canvas {
+    /// Draw a vertical line between two rows, crossing any wire in between.
+    fn vertical(&mut self, from: usize, to: usize, double: bool) {
+        for row in from..=to {
+            let symbol = match (row % 3, double) {
+                (1, false) => self.symbols.cross[usize::from(row / 3 >= self.n_qubits)],
+                (1, true) => self.symbols.double_cross[usize::from(row / 3 >= self.n_qubits)],
+                (_, false) => self.symbols.vertical,
+                (_, true) => self.symbols.double_vertical,
+            };
+            self.rows[row][self.center] = symbol;
+        }
+    }
+
+    /// Draw a labelled box across the wires from `top` to `bottom`,
+    /// numbering the target wires when there are several.
+    fn boxed(&mut self, top: usize, bottom: usize, targets: &[usize], label: &str) {
+        let symbols = self.symbols;
+        let width = self.rows[0].len();
+        let (first_row, last_row) = (3 * top, 3 * bottom + 2);
+
+        for row in first_row..=last_row {
+            let line = &mut self.rows[row];
+            let (left, fill, right) = if row == first_row {
+                (symbols.corners[0], symbols.wire, symbols.corners[1])
+            } else if row == last_row {
+                (symbols.corners[2], symbols.wire, symbols.corners[3])
+            } else if row % 3 == 1 && targets.contains(&(row / 3)) {
+                (symbols.pins[0], ' ', symbols.pins[1])
+            } else {
+                (symbols.side, ' ', symbols.side)
+            };
+            line[1] = left;
+            line[2..width - 2].fill(fill);
+            line[width - 2] = right;
+        }
+
+        if targets.len() > 1 {
+            for (ordinal, &target) in targets.iter().enumerate() {
+                if let Some(digit) = char::from_digit(ordinal as u32, 36) {
+                    self.rows[3 * target + 1][2] = digit;
+                }
+            }
+        }
+
+        let row = (first_row + last_row) / 2;
+        let length = label.chars().count();
+        let start = (width - length) / 2;
+        for (offset, c) in label.chars().enumerate() {
+            self.rows[row][start + offset] = c;
+        }
+    }
+}
+
+impl<T: Float + 'static> fmt::Display for Diagram<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbols = self.symbols();
+        let n_qubits = self.circuit.n_qubits();
+        let names: Vec<String> = (0..self.n_wires())
+            .map(|wire| match wire < n_qubits {
+                true => format!("q{wire}: "),
+                false => format!("c{}: ", wire - n_qubits),
+            })
+            .collect();
+        let margin = names.iter().map(String::len).max().unwrap_or(0);
+
+        // Split the layers into pages that fit the width.
+        let layers: Vec<Vec<Vec<char>>> = self
+            .layers()
+            .iter()
+            .map(|layer| self.draw_layer(layer))
+            .collect();
+        let mut pages: Vec<&[Vec<Vec<char>>]> = Vec::new();
+        let mut start = 0;
+        let mut used = 0;
+        for (index, layer) in layers.iter().enumerate() {
+            let width = layer.first().map_or(0, Vec::len);
+            if index > start && margin + 2 + used + width > self.width {
+                pages.push(&layers[start..index]);
+                start = index;
+                used = 0;
+            }
+            used += width;
+        }
+        pages.push(&layers[start..]);
+
+        for (page_index, page) in pages.iter().enumerate() {
+            if page_index > 0 {
+                writeln!(f)?;
+                writeln!(f)?;
+            }
+
+            let mut lines: Vec<String> = (0..3 * self.n_wires())
+                .map(|row| {
+                    let blank = self.blank(row);
+                    let wire_row = row % 3 == 1;
+                    let mut line = if wire_row {
+                        format!("{:>margin$}", names[row / 3])
+                    } else {
+                        " ".repeat(margin)
+                    };
+
+                    line.push(match (wire_row, page_index > 0) {
+                        (true, true) => symbols.continuation[0],
+                        _ => blank,
+                    });
+                    for layer in page.iter() {
+                        line.extend(&layer[row]);
+                    }
+                    line.push(match (wire_row, page_index + 1 < pages.len()) {
+                        (true, true) => symbols.continuation[1],
+                        _ => blank,
+                    });
+                    line.trim_end().to_string()
+                })
+                .collect();
+            while lines.last().is_some_and(String::is_empty) {
+                lines.pop();
+            }
+            write!(f, "{}", lines.join("\n"))?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float + 'static> QuCircuit<T> {
+    /// Get a text diagram of the circuit, which can be configured before display.
+    pub fn diagram(&self) -> Diagram<'_, T> {
+        Diagram::new(self)
+    }
+}
+
+impl<T: Float + 'static> fmt::Display for QuCircuit<T> {
+    /// Draw the circuit as a Unicode diagram wrapped to 80 columns.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.diagram().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bell_pair() {
+        let circuit = QuCircuit::<f64>::new(2).h(0).cx(0, 1).measure_all();
+
+        assert_eq!(
+            circuit.to_string(),
+            "
+      ┌───┐         ┌───┐
+q0: ──┤ H ├────■────┤ M ├─────────
+      └───┘    │    └─╥─┘
+             ┌─┴─┐    ║    ┌───┐
+q1: ─────────┤ X ├────╫────┤ M ├──
+             └───┘    ║    └─╥─┘
+                      ║      ║
+c0: ══════════════════╩══════╬════
+                             ║
+                             ║
+c1: ═════════════════════════╩════"
+                .trim_start_matches('\n')
+        );
+    }
+
+    #[test]
+    fn ascii() {
+        let circuit = QuCircuit::<f64>::with_clbits(3, 1)
+            .rx(std::f64::consts::FRAC_PI_2, 0)
+            .swap(1, 2)
+            .barrier()
+            .measure_into(2, 0)
+            .conditional(
+                &[0],
+                1,
+                Operation::Gate {
+                    name: "sdg".to_string(),
+                    params: Vec::new(),
+                    gate: crate::qugate::QuGate::s_dagger(),
+                    controls: Vec::new(),
+                    targets: vec![0],
+                },
+            );
+
+        assert_eq!(
+            circuit.diagram().ascii().to_string(),
+            "
+      +----------+  #         +-----+
+q0: --| RX(pi/2) |--#---------| Sdg |--
+      +----------+  #         +--+--+
+                    #            |
+q1: --------x-------#------------+-----
+            |       #            |
+            |       #  +---+     |
+q2: --------x-------#--| M |-----+-----
+                    #  +-+-+     |
+                         |       |
+c0: =====================v=======*====="
+                .trim_start_matches('\n')
+        );
+    }
+
+    #[test]
+    fn no_targets() {
+        let phase = crate::qugate::QuGate::new(ndarray::array![[num::Complex::new(0.0, 1.0)]]);
+        let circuit = QuCircuit::<f64>::new(2)
+            .gate("gphase", phase.clone(), &[])
+            .controlled_gate("i", Vec::new(), phase.clone(), &[0, 1], &[]);
+        let diagram = circuit.to_string();
+
+        assert!(diagram.contains("┤0  ├") && diagram.contains("│ I │"));
+        assert!(!diagram.contains('┼'));
+        assert!(!diagram.contains('■'));
+        assert_eq!(
+            QuCircuit::<f64>::new(0)
+                .gate("gphase", phase, &[])
+                .to_string(),
+            ""
+        );
+    }
+
+    #[test]
+    fn wrap() {
+        let circuit = (0..12).fold(QuCircuit::<f64>::new(1), |circuit, _| circuit.h(0));
+        let diagram = circuit.diagram().width(40).to_string();
+        let rows: Vec<&str> = diagram
+            .lines()
+            .filter(|line| line.starts_with("q0"))
+            .collect();
+
+        assert!(rows.len() > 1);
+        assert!(diagram.lines().all(|line| line.chars().count() <= 40));
+        assert!(rows[..rows.len() - 1].iter().all(|row| row.ends_with('»')));
+        assert!(rows[1..].iter().all(|row| row.starts_with("q0: «")));
+        assert_eq!(diagram.matches('H').count(), 12);
+    }
+}
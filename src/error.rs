@@ -0,0 +1,141 @@
+use std::{error::Error, fmt};
+
+/// An error raised by the fallible operations of the crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QubError {
+    /// A gate matrix is not square with a power of two size.
+    InvalidShape {
+        /// The number of rows of the matrix.
+        rows: usize,
+        /// The number of columns of the matrix.
+        columns: usize,
+    },
+    /// A gate matrix is not unitary within the requested tolerance.
+    NotUnitary,
+    /// The states of a measurement basis are not orthonormal within the requested tolerance.
+    NotOrthonormal,
+    /// An observable is not Hermitian, so its expectation values are not real.
+    NotHermitian,
+    /// A state vector does not have a non-zero power of two length.
+    InvalidLength {
+        /// The length of the state vector.
+        len: usize,
+    },
+    /// A state vector has zero norm, so it describes no physical state.
+    ZeroNorm,
+    /// A state of this many qubits has more basis states than can be indexed.
+    TooManyQubits {
+        /// The requested number of qubits.
+        n_qubits: usize,
+    },
+    /// A basis state index does not fit in the state.
+    IndexOutOfRange {
+        /// The requested basis state index.
+        index: usize,
+        /// The number of basis states.
+        len: usize,
+    },
+    /// A qubit index does not fit in the register or circuit.
+    QubitOutOfRange {
+        /// The requested qubit.
+        qubit: usize,
+        /// The number of qubits available.
+        n_qubits: usize,
+    },
+    /// A qubit appears more than once among the qubits of an operation.
+    DuplicateQubit {
+        /// The repeated qubit.
+        qubit: usize,
+    },
+    /// A classical bit index does not fit in the circuit.
+    ClbitOutOfRange {
+        /// The requested classical bit.
+        clbit: usize,
+        /// The number of classical bits available.
+        n_clbits: usize,
+    },
+    /// A condition compares classical bits with a value that needs more bits than it reads.
+    ConditionOutOfRange {
+        /// The value of the condition.
+        value: usize,
+        /// The number of classical bits the condition reads.
+        n_clbits: usize,
+    },
+    /// A channel was given no Kraus operators.
+    EmptyChannel,
+    /// A Pauli string contains a symbol other than I, X, Y or Z.
+    InvalidPauli {
+        /// The unexpected symbol.
+        symbol: char,
+    },
+    /// A number could not be converted to or from the floating point type in use.
+    NumericConversion,
+    /// A gate has a parameter or matrix entry that is infinite or NaN.
+    NonFinite,
+    /// An operation acts on a different number of qubits than it was given.
+    DimensionMismatch {
+        /// The number of qubits the operation acts on.
+        expected: usize,
+        /// The number of qubits it was given.
+        found: usize,
+    },
+    /// A circuit program could not be parsed.
+    Parse {
+        /// The line of the program where parsing failed, starting from 1.
+        line: usize,
+        /// A description of the problem.
+        message: String,
+    },
+}
+
+impl fmt::Display for QubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidShape { rows, columns } => write!(
+                f,
+                "matrix of shape {rows}x{columns} is not square with a power of two size"
+            ),
+            Self::NotUnitary => write!(f, "matrix is not unitary"),
+            Self::NotOrthonormal => write!(f, "basis states are not orthonormal"),
+            Self::NotHermitian => write!(f, "observable is not Hermitian"),
+            Self::InvalidLength { len } => {
+                write!(f, "state of length {len} is not a non-zero power of two")
+            }
+            Self::ZeroNorm => write!(f, "state has zero norm"),
+            Self::TooManyQubits { n_qubits } => {
+                write!(f, "too many qubits: {n_qubits} qubits cannot be indexed")
+            }
+            Self::IndexOutOfRange { index, len } => write!(
+                f,
+                "basis state index out of range: {index} for {len} basis states"
+            ),
+            Self::QubitOutOfRange { qubit, n_qubits } => {
+                write!(f, "qubit out of range: {qubit} for {n_qubits} qubits")
+            }
+            Self::DuplicateQubit { qubit } => {
+                write!(f, "qubits must be distinct: {qubit} is repeated")
+            }
+            Self::ClbitOutOfRange { clbit, n_clbits } => write!(
+                f,
+                "classical bit out of range: {clbit} for {n_clbits} classical bits"
+            ),
+            Self::ConditionOutOfRange { value, n_clbits } => write!(
+                f,
+                "condition value out of range: {value} for {n_clbits} classical bits"
+            ),
+            Self::EmptyChannel => write!(f, "channel must have at least one Kraus operator"),
+            Self::InvalidPauli { symbol } => {
+                write!(f, "invalid Pauli symbol {symbol:?}, expected I, X, Y or Z")
+            }
+            Self::NumericConversion => write!(f, "numeric conversion failed"),
+            Self::NonFinite => write!(f, "gate parameters and matrix entries must be finite"),
+            Self::DimensionMismatch { expected, found } => write!(
+                f,
+                "size mismatch: expected {expected} qubits, found {found}"
+            ),
+            Self::Parse { line, message } => write!(f, "parse error on line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for QubError {}
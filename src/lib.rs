@@ -1,2 +1,15 @@
+pub mod basis;
+pub mod channel;
+mod compare;
+pub mod counts;
+pub mod density_matrix;
+pub mod diagram;
+pub mod error;
+pub mod pauli;
+mod qasm;
 pub mod qubit;
+pub mod qucircuit;
 pub mod qugate;
+mod quil;
+pub mod quregister;
+mod svg;
@@ -0,0 +1,529 @@
+use std::{fmt, ops, str::FromStr};
+
+use ndarray::prelude::*;
+use num::{complex::Complex, Float};
+
+use crate::{error::QubError, qugate::QuGate, quregister::QuRegister};
+
+/// A single-qubit Pauli operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Pauli {
+    /// The identity operator.
+    I,
+    /// The Pauli-X operator.
+    X,
+    /// The Pauli-Y operator.
+    Y,
+    /// The Pauli-Z operator.
+    Z,
+}
+
+impl Pauli {
+    /// Multiply two Pauli operators, returning the phase as a power of i
+    /// and the resulting operator, e.g. XY = iZ.
+    fn mul(self, other: Self) -> (u8, Self) {
+        use Pauli::*;
+
+        match (self, other) {
+            (I, p) | (p, I) => (0, p),
+            (X, X) | (Y, Y) | (Z, Z) => (0, I),
+            (X, Y) => (1, Z),
+            (Y, Z) => (1, X),
+            (Z, X) => (1, Y),
+            (Y, X) => (3, Z),
+            (Z, Y) => (3, X),
+            (X, Z) => (3, Y),
+        }
+    }
+
+    /// Get the gate of the Pauli operator.
+    pub fn to_gate<T: Float + 'static>(self) -> QuGate<T> {
+        match self {
+            Self::I => QuGate::identity(1),
+            Self::X => QuGate::pauli_x(),
+            Self::Y => QuGate::pauli_y(),
+            Self::Z => QuGate::pauli_z(),
+        }
+    }
+}
+
+impl TryFrom<char> for Pauli {
+    type Error = QubError;
+
+    fn try_from(symbol: char) -> Result<Self, QubError> {
+        match symbol {
+            'I' => Ok(Self::I),
+            'X' => Ok(Self::X),
+            'Y' => Ok(Self::Y),
+            'Z' => Ok(Self::Z),
+            _ => Err(QubError::InvalidPauli { symbol }),
+        }
+    }
+}
+
+impl fmt::Display for Pauli {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// A Pauli string is a tensor product of single-qubit Pauli operators,
+/// one per qubit starting from qubit 0, with a phase of 1, i, -1 or -i.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PauliString {
+    /// The phase of the string, as a power of i.
+    phase: u8,
+    paulis: Vec<Pauli>,
+}
+
+impl PauliString {
+    /// Create a new Pauli string with the given operators and a phase of 1.
+    pub fn new(paulis: Vec<Pauli>) -> Self {
+        Self { phase: 0, paulis }
+    }
+
+    /// Create the identity Pauli string over n qubits.
+    pub fn identity(n_qubits: usize) -> Self {
+        Self::new(vec![Pauli::I; n_qubits])
+    }
+
+    /// Multiply the phase of the string by i^power.
+    pub fn with_phase(mut self, power: u8) -> Self {
+        self.phase = (self.phase + power % 4) % 4;
+        self
+    }
+
+    /// Get the phase of the string, as a power of i.
+    pub fn phase(&self) -> u8 {
+        self.phase
+    }
+
+    /// Get the Pauli operators of the string.
+    pub fn paulis(&self) -> &[Pauli] {
+        &self.paulis
+    }
+
+    /// Get the number of qubits the string acts on.
+    pub fn n_qubits(&self) -> usize {
+        self.paulis.len()
+    }
+
+    /// Check whether the string is Hermitian, i.e. its phase is ±1.
+    pub fn is_hermitian(&self) -> bool {
+        matches!(self.phase, 0 | 2)
+    }
+
+    /// Check whether this string commutes with another,
+    /// which is the case when they anticommute on an even number of qubits.
+    ///
+    /// # Panics
+    /// Panics if the strings do not act on the same number of qubits.
+    pub fn commutes_with(&self, other: &Self) -> bool {
+        self.check_size(other.n_qubits());
+
+        let anticommuting = self
+            .paulis
+            .iter()
+            .zip(&other.paulis)
+            .filter(|&(&a, &b)| a != Pauli::I && b != Pauli::I && a != b)
+            .count();
+        anticommuting % 2 == 0
+    }
+
+    /// Get the phase as a complex number.
+    fn phase_factor<T: Float>(&self) -> Complex<T> {
+        let (zero, one) = (T::zero(), T::one());
+        match self.phase {
+            0 => Complex::new(one, zero),
+            1 => Complex::new(zero, one),
+            2 => Complex::new(-one, zero),
+            _ => Complex::new(zero, -one),
+        }
+    }
+
+    /// Get the dense gate of the string, of size 2^n x 2^n.
+    pub fn to_gate<T: Float + 'static>(&self) -> QuGate<T> {
+        let gate = self
+            .paulis
+            .iter()
+            .fold(QuGate::<T>::identity(0), |acc, pauli| {
+                acc.tensor(&pauli.to_gate())
+            });
+        let phase = self.phase_factor::<T>();
+
+        QuGate::new(gate.matrix().mapv(|x| x * phase))
+    }
+
+    /// Apply the string to a register in a single pass over the state,
+    /// without building the dense 2^n x 2^n operator.
+    ///
+    /// # Panics
+    /// Panics if the string does not act on as many qubits as the register holds.
+    pub fn apply<T: Float + 'static>(&self, register: &QuRegister<T>) -> QuRegister<T> {
+        self.check_size(register.n_qubits());
+
+        // X and Y flip their qubit, Y and Z add a sign when their qubit is |1⟩,
+        // and each Y adds a phase of i.
+        let (mut flip_mask, mut sign_mask, mut n_y) = (0, 0, 0);
+        for (qubit, pauli) in self.paulis.iter().enumerate() {
+            let mask = 1 << (self.n_qubits() - 1 - qubit);
+            match pauli {
+                Pauli::I => {}
+                Pauli::X => flip_mask |= mask,
+                Pauli::Y => {
+                    flip_mask |= mask;
+                    sign_mask |= mask;
+                    n_y += 1;
+                }
+                Pauli::Z => sign_mask |= mask,
+            }
+        }
+        let phase = self.clone().with_phase(n_y % 4).phase_factor::<T>();
+
+        let mut state = Array1::from_elem(register.state.len(), Complex::new(T::zero(), T::zero()));
+        for (index, &amplitude) in register.state.indexed_iter() {
+            let sign = if (index & sign_mask).count_ones() % 2 == 0 {
+                phase
+            } else {
+                -phase
+            };
+            state[index ^ flip_mask] = amplitude * sign;
+        }
+
+        QuRegister { state }
+    }
+
+    /// Get the expectation value ⟨ψ|P|ψ⟩ of the string on a register,
+    /// which is real when the string is Hermitian.
+    ///
+    /// # Panics
+    /// Panics if the string does not act on as many qubits as the register holds.
+    pub fn expectation<T: Float + 'static>(&self, register: &QuRegister<T>) -> Complex<T> {
+        register.inner(&self.apply(register))
+    }
+
+    fn check_size(&self, n_qubits: usize) {
+        if self.n_qubits() != n_qubits {
+            panic!(
+                "{}",
+                QubError::DimensionMismatch {
+                    expected: self.n_qubits(),
+                    found: n_qubits,
+                }
+            );
+        }
+    }
+}
+
+impl ops::Mul for &PauliString {
+    type Output = PauliString;
+
+    /// Multiply two Pauli strings qubit by qubit.
+    ///
+    /// # Panics
+    /// Panics if the strings do not act on the same number of qubits.
+    fn mul(self, other: Self) -> PauliString {
+        self.check_size(other.n_qubits());
+
+        let (powers, paulis): (Vec<u8>, Vec<Pauli>) = self
+            .paulis
+            .iter()
+            .zip(&other.paulis)
+            .map(|(&a, &b)| a.mul(b))
+            .unzip();
+
+        powers.into_iter().fold(
+            PauliString::new(paulis)
+                .with_phase(self.phase)
+                .with_phase(other.phase),
+            PauliString::with_phase,
+        )
+    }
+}
+
+impl FromStr for PauliString {
+    type Err = QubError;
+
+    /// Parse a Pauli string such as `"XZIY"`, optionally prefixed
+    /// by a phase of `+`, `-`, `i`, `+i` or `-i`.
+    fn from_str(s: &str) -> Result<Self, QubError> {
+        let (phase, paulis) = if let Some(rest) = s.strip_prefix("-i") {
+            (3, rest)
+        } else if let Some(rest) = s.strip_prefix("+i") {
+            (1, rest)
+        } else if let Some(rest) = s.strip_prefix('i') {
+            (1, rest)
+        } else if let Some(rest) = s.strip_prefix('-') {
+            (2, rest)
+        } else {
+            (0, s.strip_prefix('+').unwrap_or(s))
+        };
+
+        let paulis = paulis
+            .chars()
+            .map(Pauli::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(paulis).with_phase(phase))
+    }
+}
+
+impl fmt::Display for PauliString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = ["", "i", "-", "-i"][self.phase as usize];
+        write!(f, "{prefix}")?;
+        for pauli in &self.paulis {
+            write!(f, "{pauli}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A Pauli sum is a linear combination Σ cᵢ Pᵢ of Pauli strings
+/// with complex coefficients, such as a Hamiltonian.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PauliSum<T: Float> {
+    /// The terms of the sum, with the phase of each string folded into its coefficient.
+    terms: Vec<(Complex<T>, PauliString)>,
+}
+
+impl<T: Float + 'static> PauliSum<T> {
+    /// Create a new empty sum, which is the zero operator.
+    pub fn new() -> Self {
+        Self { terms: Vec::new() }
+    }
+
+    /// Create a new sum from the given terms, combining like terms.
+    ///
+    /// # Panics
+    /// Panics if the strings do not all act on the same number of qubits.
+    pub fn from_terms(terms: impl IntoIterator<Item = (Complex<T>, PauliString)>) -> Self {
+        let mut sum = Self::new();
+        for (coefficient, string) in terms {
+            sum.add_term(coefficient, string);
+        }
+        sum
+    }
+
+    /// Add a term c P to the sum, combining it with an existing term on the same operators.
+    ///
+    /// # Panics
+    /// Panics if the string does not act on as many qubits as the other terms.
+    pub fn add_term(&mut self, coefficient: Complex<T>, string: PauliString) {
+        if let Some((_, first)) = self.terms.first() {
+            first.check_size(string.n_qubits());
+        }
+
+        let coefficient = coefficient * string.phase_factor();
+        let string = PauliString::new(string.paulis);
+        match self.terms.iter_mut().find(|(_, other)| *other == string) {
+            Some((existing, _)) => *existing = *existing + coefficient,
+            None => self.terms.push((coefficient, string)),
+        }
+    }
+
+    /// Get the terms of the sum, each string having a phase of 1.
+    pub fn terms(&self) -> &[(Complex<T>, PauliString)] {
+        &self.terms
+    }
+
+    /// Get the number of qubits the sum acts on, or `None` if it has no terms.
+    pub fn n_qubits(&self) -> Option<usize> {
+        self.terms.first().map(|(_, string)| string.n_qubits())
+    }
+
+    /// Check whether the sum has no terms.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Remove the terms whose coefficients are within the given tolerance of zero.
+    pub fn simplify(mut self, tolerance: T) -> Self {
+        self.terms
+            .retain(|(coefficient, _)| coefficient.norm() > tolerance);
+        self
+    }
+
+    /// Multiply every coefficient of the sum by a scalar.
+    pub fn scale(mut self, factor: Complex<T>) -> Self {
+        for (coefficient, _) in &mut self.terms {
+            *coefficient = *coefficient * factor;
+        }
+        self
+    }
+
+    /// Check whether the sum is Hermitian within the given tolerance,
+    /// i.e. all its coefficients are real.
+    pub fn is_hermitian(&self, tolerance: T) -> bool {
+        self.terms
+            .iter()
+            .all(|(coefficient, _)| coefficient.im.abs() <= tolerance)
+    }
+
+    /// Check whether this sum commutes with another within the given tolerance,
+    /// by checking that their commutator AB - BA vanishes.
+    pub fn commutes_with(&self, other: &Self, tolerance: T) -> bool {
+        let commutator = self.clone() * other.clone() - other.clone() * self.clone();
+        commutator.simplify(tolerance).is_empty()
+    }
+
+    /// Get the dense gate of the sum, of size 2^n x 2^n.
+    ///
+    /// # Panics
+    /// Panics if the sum has no terms.
+    pub fn to_gate(&self) -> QuGate<T> {
+        let n_qubits = self.n_qubits().expect("sum must have at least one term");
+        let dim = 1 << n_qubits;
+
+        let matrix =
+            self.terms
+                .iter()
+                .fold(Array2::zeros((dim, dim)), |acc, (coefficient, string)| {
+                    acc + string.to_gate::<T>().matrix().mapv(|x| x * coefficient)
+                });
+        QuGate::new(matrix)
+    }
+
+    /// Apply the sum to a register, one Pauli string at a time,
+    /// without building the dense 2^n x 2^n operator.
+    /// The result is generally not normalised.
+    ///
+    /// # Panics
+    /// Panics if the sum does not act on as many qubits as the register holds.
+    pub fn apply(&self, register: &QuRegister<T>) -> QuRegister<T> {
+        let zero = Array1::from_elem(register.state.len(), Complex::new(T::zero(), T::zero()));
+        let state = self.terms.iter().fold(zero, |acc, (coefficient, string)| {
+            acc + string.apply(register).state.mapv(|x| x * coefficient)
+        });
+
+        QuRegister { state }
+    }
+
+    /// Get the expectation value ⟨ψ|H|ψ⟩ of the sum on a register,
+    /// whose real part is returned since a Hermitian sum has a real expectation.
+    ///
+    /// # Panics
+    /// Panics if the sum does not act on as many qubits as the register holds.
+    pub fn expectation(&self, register: &QuRegister<T>) -> T {
+        self.terms
+            .iter()
+            .fold(
+                Complex::new(T::zero(), T::zero()),
+                |acc, (coefficient, string)| acc + coefficient * string.expectation(register),
+            )
+            .re
+    }
+}
+
+impl<T: Float + 'static> Default for PauliSum<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float + 'static> ops::Add for PauliSum<T> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        for (coefficient, string) in other.terms {
+            self.add_term(coefficient, string);
+        }
+        self
+    }
+}
+
+impl<T: Float + 'static> ops::Neg for PauliSum<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.scale(Complex::new(-T::one(), T::zero()))
+    }
+}
+
+impl<T: Float + 'static> ops::Sub for PauliSum<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl<T: Float + 'static> ops::Mul for PauliSum<T> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut product = Self::new();
+        for (a, p) in &self.terms {
+            for (b, q) in &other.terms {
+                product.add_term(a * b, p * q);
+            }
+        }
+        product
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pauli(s: &str) -> PauliString {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(pauli("-iXZ").to_string(), "-iXZ");
+        assert_eq!(pauli("+IY"), PauliString::new(vec![Pauli::I, Pauli::Y]));
+        assert_eq!(
+            "XA".parse::<PauliString>(),
+            Err(QubError::InvalidPauli { symbol: 'A' })
+        );
+    }
+
+    #[test]
+    fn multiply() {
+        assert_eq!(&pauli("X") * &pauli("Y"), pauli("iZ"));
+        assert_eq!(&pauli("XZ") * &pauli("ZX"), pauli("YY"));
+        assert_eq!(&pauli("-iX") * &pauli("Y"), pauli("Z"));
+        assert!(pauli("XX").commutes_with(&pauli("ZZ")));
+        assert!(!pauli("XI").commutes_with(&pauli("ZZ")));
+        assert_eq!(pauli("iX").with_phase(u8::MAX), pauli("X"));
+    }
+
+    #[test]
+    fn apply() {
+        let register = QuGate::hadamard().apply_to(&QuRegister::<f64>::basis(3, 0b011), 1);
+
+        for string in ["XYZ", "-iZIY", "YYX", "III"] {
+            let string = pauli(string);
+            assert!(string
+                .apply(&register)
+                .approx_eq(&string.to_gate().apply_register(&register), 1e-12));
+        }
+    }
+
+    #[test]
+    fn sum() {
+        let one = Complex::new(1.0, 0.0);
+        let x = PauliSum::<f64>::from_terms([(one, pauli("X"))]);
+        let z = PauliSum::from_terms([(one, pauli("Z"))]);
+        let hamiltonian = x.clone() + z.clone().scale(Complex::new(2.0, 0.0));
+
+        assert!(!x.commutes_with(&z, 1e-12));
+        assert!(hamiltonian.commutes_with(&hamiltonian, 1e-12));
+        assert!(
+            (x.clone() * x.clone() - PauliSum::from_terms([(one, pauli("I"))]))
+                .simplify(1e-12)
+                .is_empty()
+        );
+        assert_eq!(
+            (x.clone() * z.clone()).terms(),
+            &[(Complex::new(0.0, -1.0), pauli("Y"))]
+        );
+
+        let zero = QuRegister::zero(1);
+        assert!((hamiltonian.expectation(&zero) - 2.0).abs() < 1e-12);
+        assert!(hamiltonian
+            .apply(&zero)
+            .approx_eq(&hamiltonian.to_gate().apply_register(&zero), 1e-12));
+    }
+}
@@ -0,0 +1,1356 @@
+use std::{
+    collections::HashMap,
+    f64::consts::{FRAC_PI_2, PI},
+    fmt::Write,
+    iter,
+};
+
+use ndarray::prelude::*;
+use num::{complex::Complex, Float};
+
+use crate::{
+    error::QubError,
+    qucircuit::{named_gate, Operation, QuCircuit},
+    qugate::QuGate,
+};
+
+/// The gates of the standard `qelib1.inc` library and the built-in `U` and `CX`,
+/// with their number of parameters and qubits.
+const STANDARD_GATES: &[(&str, usize, usize)] = &[
+    ("U", 3, 1),
+    ("CX", 0, 2),
+    ("u3", 3, 1),
+    ("u2", 2, 1),
+    ("u1", 1, 1),
+    ("u", 3, 1),
+    ("p", 1, 1),
+    ("u0", 1, 1),
+    ("id", 0, 1),
+    ("x", 0, 1),
+    ("y", 0, 1),
+    ("z", 0, 1),
+    ("h", 0, 1),
+    ("s", 0, 1),
+    ("sdg", 0, 1),
+    ("t", 0, 1),
+    ("tdg", 0, 1),
+    ("sx", 0, 1),
+    ("sxdg", 0, 1),
+    ("rx", 1, 1),
+    ("ry", 1, 1),
+    ("rz", 1, 1),
+    ("cx", 0, 2),
+    ("cy", 0, 2),
+    ("cz", 0, 2),
+    ("ch", 0, 2),
+    ("swap", 0, 2),
+    ("crx", 1, 2),
+    ("cry", 1, 2),
+    ("crz", 1, 2),
+    ("cu1", 1, 2),
+    ("cp", 1, 2),
+    ("cu3", 3, 2),
+    ("rxx", 1, 2),
+    ("rzz", 1, 2),
+    ("csx", 0, 2),
+    ("ccx", 0, 3),
+    ("cswap", 0, 3),
+    ("c3x", 0, 4),
+    ("c3sqrtx", 0, 4),
+    ("c4x", 0, 5),
+];
+
+/// Get the name, parameters and number of controls
+/// a standard gate is recorded with in a circuit.
+/// The parameters must match the count in `STANDARD_GATES`.
+fn standard_gate<T: Float>(
+    name: &str,
+    params: &[T],
+) -> Result<(&'static str, Vec<T>, usize), QubError> {
+    let gate = match (name, params) {
+        ("U" | "u3" | "u", _) => ("u3", params.to_vec(), 0),
+        ("u2", &[phi, lambda]) => {
+            let theta = T::from(FRAC_PI_2).ok_or(QubError::NumericConversion)?;
+            ("u3", vec![theta, phi, lambda], 0)
+        }
+        ("u1" | "p", _) => ("p", params.to_vec(), 0),
+        ("u0", _) => ("id", Vec::new(), 0),
+        ("CX" | "cx", _) => ("x", Vec::new(), 1),
+        ("cy", _) => ("y", Vec::new(), 1),
+        ("cz", _) => ("z", Vec::new(), 1),
+        ("ch", _) => ("h", Vec::new(), 1),
+        ("crx", _) => ("rx", params.to_vec(), 1),
+        ("cry", _) => ("ry", params.to_vec(), 1),
+        ("crz", _) => ("rz", params.to_vec(), 1),
+        ("cu1" | "cp", _) => ("p", params.to_vec(), 1),
+        ("cu3", _) => ("u3", params.to_vec(), 1),
+        ("csx", _) => ("sx", Vec::new(), 1),
+        ("ccx", _) => ("x", Vec::new(), 2),
+        ("cswap", _) => ("swap", Vec::new(), 1),
+        ("c3x", _) => ("x", Vec::new(), 3),
+        ("c3sqrtx", _) => ("sx", Vec::new(), 3),
+        ("c4x", _) => ("x", Vec::new(), 4),
+        _ => {
+            let name = STANDARD_GATES
+                .iter()
+                .find(|(standard, ..)| *standard == name)
+                .map(|(standard, ..)| *standard)
+                .expect("standard gates are checked before building");
+            (name, params.to_vec(), 0)
+        }
+    };
+    Ok(gate)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(usize),
+    Real(f64),
+    Str(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+    "->", "==", ";", ",", "(", ")", "[", "]", "{", "}", "+", "-", "*", "/", "^",
+];
+
+/// Get the length of the decimal number at the start of the text,
+/// with an optional fractional part and exponent.
+pub(crate) fn number_length(text: &str) -> usize {
+    let length = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let exponent = text[length..]
+        .strip_prefix(['e', 'E'])
+        .map(|exponent| exponent.strip_prefix(['+', '-']).unwrap_or(exponent))
+        .filter(|digits| digits.starts_with(|c: char| c.is_ascii_digit()));
+
+    match exponent {
+        Some(digits) => {
+            text.len() - digits.len()
+                + digits
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(digits.len())
+        }
+        None => length,
+    }
+}
+
+/// Split a program into tokens, each with the line it starts on.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, QubError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut rest = source;
+
+    while let Some(next) = rest.chars().next() {
+        let error = |message: String| QubError::Parse { line, message };
+
+        let length = if next == '\n' {
+            line += 1;
+            1
+        } else if next.is_whitespace() {
+            next.len_utf8()
+        } else if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if next.is_ascii_alphabetic() || next == '_' {
+            let length = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push((line, Token::Ident(rest[..length].to_string())));
+            length
+        } else if next.is_ascii_digit()
+            || (next == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let length = number_length(rest);
+            let text = &rest[..length];
+            let token = if text.contains(['.', 'e', 'E']) {
+                text.parse().map(Token::Real).ok()
+            } else {
+                text.parse().map(Token::Int).ok()
+            };
+            tokens.push((
+                line,
+                token.ok_or_else(|| error(format!("invalid number {text}")))?,
+            ));
+            length
+        } else if next == '"' {
+            let end = rest[1..]
+                .find(['"', '\n'])
+                .filter(|&end| rest[1 + end..].starts_with('"'))
+                .ok_or_else(|| error("unterminated string".to_string()))?;
+            tokens.push((line, Token::Str(rest[1..1 + end].to_string())));
+            end + 2
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(*symbol))
+                .ok_or_else(|| error(format!("unexpected character {next:?}")))?;
+            tokens.push((line, Token::Symbol(symbol)));
+            symbol.len()
+        };
+
+        rest = &rest[length..];
+    }
+
+    Ok(tokens)
+}
+
+/// A parameter expression, evaluated once the parameters of a gate are known.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Pi,
+    Param(String),
+    Neg(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Box<Expr>),
+}
+
+const FUNCTIONS: &[&str] = &["sin", "cos", "tan", "exp", "ln", "sqrt"];
+
+impl Expr {
+    fn eval<T: Float>(&self, params: &HashMap<&str, T>) -> Result<T, QubError> {
+        let value = match self {
+            Self::Number(value) => T::from(*value).ok_or(QubError::NumericConversion)?,
+            Self::Pi => T::from(PI).ok_or(QubError::NumericConversion)?,
+            Self::Param(name) => params[name.as_str()],
+            Self::Neg(expr) => -expr.eval(params)?,
+            Self::Binary(op, left, right) => {
+                let (left, right) = (left.eval(params)?, right.eval(params)?);
+                match *op {
+                    "+" => left + right,
+                    "-" => left - right,
+                    "*" => left * right,
+                    "/" => left / right,
+                    _ => left.powf(right),
+                }
+            }
+            Self::Call(function, argument) => {
+                let argument = argument.eval(params)?;
+                match function.as_str() {
+                    "sin" => argument.sin(),
+                    "cos" => argument.cos(),
+                    "tan" => argument.tan(),
+                    "exp" => argument.exp(),
+                    "ln" => argument.ln(),
+                    _ => argument.sqrt(),
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// A gate defined in the program with the `gate` statement.
+#[derive(Debug, Clone)]
+struct GateDefinition {
+    params: Vec<String>,
+    n_qubits: usize,
+    /// The body of the gate, as gate names applied with parameter expressions
+    /// to indices into the qubit arguments of the gate.
+    body: Vec<(String, Vec<Expr>, Vec<usize>)>,
+}
+
+struct Parser<T: Float> {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// The offset and size of each quantum register.
+    qregs: HashMap<String, (usize, usize)>,
+    /// The offset and size of each classical register.
+    cregs: HashMap<String, (usize, usize)>,
+    n_qubits: usize,
+    n_clbits: usize,
+    gates: HashMap<String, GateDefinition>,
+    /// The operations of the circuit, with the line they appear on.
+    operations: Vec<(usize, Operation<T>)>,
+}
+
+impl<T: Float + 'static> Parser<T> {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |(line, _)| *line)
+    }
+
+    fn error(&self, message: impl Into<String>) -> QubError {
+        QubError::Parse {
+            line: self.line(),
+            message: message.into(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Token, QubError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(_, token)| token.clone())
+            .ok_or_else(|| self.error("unexpected end of program"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found =
+            matches!(self.tokens.get(self.position), Some((_, Token::Symbol(s))) if *s == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), QubError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {symbol:?}")))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, QubError> {
+        match self.next()? {
+            Token::Ident(name) => Ok(name),
+            _ => {
+                self.position -= 1;
+                Err(self.error("expected an identifier"))
+            }
+        }
+    }
+
+    fn int(&mut self) -> Result<usize, QubError> {
+        match self.next()? {
+            Token::Int(value) => Ok(value),
+            _ => {
+                self.position -= 1;
+                Err(self.error("expected an integer"))
+            }
+        }
+    }
+
+    fn program(&mut self) -> Result<(), QubError> {
+        if matches!(self.tokens.first(), Some((_, Token::Ident(name))) if name == "OPENQASM") {
+            self.position += 1;
+            let version = match self.next()? {
+                Token::Real(version) => version,
+                Token::Int(version) => version as f64,
+                _ => return Err(self.error("expected a version number")),
+            };
+            if !(2.0..3.0).contains(&version) {
+                return Err(self.error(format!("unsupported OpenQASM version {version}")));
+            }
+            self.expect(";")?;
+        }
+
+        while self.position < self.tokens.len() {
+            self.statement()?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), QubError> {
+        let name = self.ident()?;
+        match name.as_str() {
+            "include" => {
+                match self.next()? {
+                    Token::Str(file) if file == "qelib1.inc" => {}
+                    Token::Str(file) => return Err(self.error(format!("cannot include {file:?}"))),
+                    _ => return Err(self.error("expected a file name")),
+                }
+                self.expect(";")
+            }
+            "qreg" | "creg" => self.register(name == "qreg"),
+            "gate" => self.gate_definition(),
+            "opaque" => Err(self.error("opaque gates are not supported")),
+            "if" => {
+                self.expect("(")?;
+                let (offset, size) = self.creg()?;
+                self.expect("==")?;
+                let value = self.int()?;
+                if u32::try_from(size)
+                    .ok()
+                    .and_then(|shift| value.checked_shr(shift))
+                    .unwrap_or(0)
+                    != 0
+                {
+                    return Err(self.error(format!("value {value} does not fit in {size} bits")));
+                }
+                self.expect(")")?;
+
+                let (start, line) = (self.operations.len(), self.line());
+                let name = self.ident()?;
+                self.quantum_operation(&name)?;
+
+                // The condition is checked once for the whole statement, but each
+                // operation is recorded with its own copy of it, so no operation may
+                // follow a measurement that changes the register being compared.
+                let operations = &self.operations[start..];
+                let measured = operations.iter().position(|(_, operation)| {
+                    matches!(operation, Operation::Measure { clbit, .. }
+                        if (offset..offset + size).contains(clbit))
+                });
+                if measured.is_some_and(|index| index + 1 < operations.len()) {
+                    return Err(QubError::Parse {
+                        line,
+                        message: "measurements into the register read by if cannot be broadcast"
+                            .to_string(),
+                    });
+                }
+                for (_, operation) in &mut self.operations[start..] {
+                    *operation = Operation::Conditional {
+                        clbits: (offset..offset + size).collect(),
+                        value,
+                        operation: Box::new(operation.clone()),
+                    };
+                }
+                Ok(())
+            }
+            _ => self.quantum_operation(&name),
+        }
+    }
+
+    fn register(&mut self, quantum: bool) -> Result<(), QubError> {
+        let name = self.ident()?;
+        self.expect("[")?;
+        let size = self.int()?;
+        self.expect("]")?;
+        self.expect(";")?;
+
+        if self.qregs.contains_key(&name) || self.cregs.contains_key(&name) {
+            return Err(self.error(format!("register {name} is already declared")));
+        }
+        if quantum {
+            self.qregs.insert(name, (self.n_qubits, size));
+            self.n_qubits += size;
+        } else {
+            self.cregs.insert(name, (self.n_clbits, size));
+            self.n_clbits += size;
+        }
+        Ok(())
+    }
+
+    fn creg(&mut self) -> Result<(usize, usize), QubError> {
+        let name = self.ident()?;
+        self.cregs
+            .get(&name)
+            .copied()
+            .ok_or_else(|| self.error(format!("unknown classical register {name}")))
+    }
+
+    /// Parse a register or a single bit of a register,
+    /// returning the indices of the bits it refers to.
+    fn argument(&mut self, quantum: bool) -> Result<Vec<usize>, QubError> {
+        let name = self.ident()?;
+        let registers = if quantum { &self.qregs } else { &self.cregs };
+        let (offset, size) = *registers.get(&name).ok_or_else(|| {
+            let kind = if quantum { "quantum" } else { "classical" };
+            self.error(format!("unknown {kind} register {name}"))
+        })?;
+
+        if self.eat("[") {
+            let index = self.int()?;
+            self.expect("]")?;
+            if index >= size {
+                return Err(self.error(format!(
+                    "index {index} out of range for register {name} of size {size}"
+                )));
+            }
+            Ok(vec![offset + index])
+        } else {
+            Ok((offset..offset + size).collect())
+        }
+    }
+
+    /// Broadcast arguments over whole registers,
+    /// returning the bits of each application in turn.
+    fn broadcast(&self, arguments: Vec<Vec<usize>>) -> Result<Vec<Vec<usize>>, QubError> {
+        let size = arguments.iter().map(Vec::len).max().unwrap_or(1);
+        if arguments
+            .iter()
+            .any(|bits| bits.len() != 1 && bits.len() != size)
+        {
+            return Err(self.error("registers must have the same size"));
+        }
+
+        Ok((0..size)
+            .map(|i| {
+                arguments
+                    .iter()
+                    .map(|bits| if bits.len() == 1 { bits[0] } else { bits[i] })
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn quantum_operation(&mut self, name: &str) -> Result<(), QubError> {
+        let line = self.line();
+        match name {
+            "measure" => {
+                let qubits = self.argument(true)?;
+                self.expect("->")?;
+                let clbits = self.argument(false)?;
+                self.expect(";")?;
+
+                if qubits.len() != clbits.len() {
+                    return Err(self.error("registers must have the same size"));
+                }
+                for (qubit, clbit) in qubits.into_iter().zip(clbits) {
+                    self.operations
+                        .push((line, Operation::Measure { qubit, clbit }));
+                }
+                Ok(())
+            }
+            "barrier" => {
+                let mut qubits = self.argument(true)?;
+                while self.eat(",") {
+                    qubits.extend(self.argument(true)?);
+                }
+                self.expect(";")?;
+                self.operations.push((line, Operation::Barrier(qubits)));
+                Ok(())
+            }
+            "reset" => Err(self.error("reset is not supported")),
+            _ => {
+                let params = if self.eat("(") {
+                    let params = self.expressions(&[])?;
+                    self.expect(")")?;
+                    params
+                        .iter()
+                        .map(|expr| expr.eval(&HashMap::new()))
+                        .collect::<Result<_, _>>()?
+                } else {
+                    Vec::new()
+                };
+                let mut arguments = vec![self.argument(true)?];
+                while self.eat(",") {
+                    arguments.push(self.argument(true)?);
+                }
+                self.expect(";")?;
+
+                for qubits in self.broadcast(arguments)? {
+                    self.apply_gate(name, &params, &qubits, line)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Record a gate application, expanding gates defined in the program.
+    fn apply_gate(
+        &mut self,
+        name: &str,
+        params: &[T],
+        qubits: &[usize],
+        line: usize,
+    ) -> Result<(), QubError> {
+        let error = |message: String| QubError::Parse { line, message };
+
+        let (n_params, n_qubits) = if let Some(definition) = self.gates.get(name) {
+            (definition.params.len(), definition.n_qubits)
+        } else if let Some(&(_, n_params, n_qubits)) = STANDARD_GATES
+            .iter()
+            .find(|(standard, ..)| *standard == name)
+        {
+            (n_params, n_qubits)
+        } else {
+            return Err(error(format!("unknown gate {name}")));
+        };
+        if params.len() != n_params || qubits.len() != n_qubits {
+            return Err(error(format!(
+                "gate {name} takes {n_params} parameters and {n_qubits} qubits, found {} and {}",
+                params.len(),
+                qubits.len()
+            )));
+        }
+
+        if let Some(definition) = self.gates.get(name).cloned() {
+            let values = definition
+                .params
+                .iter()
+                .map(String::as_str)
+                .zip(params.iter().copied())
+                .collect();
+            for (name, params, arguments) in &definition.body {
+                let qubits: Vec<usize> = arguments.iter().map(|&i| qubits[i]).collect();
+                if name == "barrier" {
+                    self.operations.push((line, Operation::Barrier(qubits)));
+                } else {
+                    let params: Vec<T> = params
+                        .iter()
+                        .map(|expr| expr.eval(&values))
+                        .collect::<Result<_, _>>()?;
+                    self.apply_gate(name, &params, &qubits, line)?;
+                }
+            }
+        } else {
+            let (name, params, n_controls) = standard_gate(name, params)?;
+            let gate = named_gate(name, &params).expect("standard gates have a built-in gate");
+            self.operations.push((
+                line,
+                Operation::Gate {
+                    name: name.to_string(),
+                    params,
+                    gate,
+                    controls: qubits[..n_controls].to_vec(),
+                    targets: qubits[n_controls..].to_vec(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    fn gate_definition(&mut self) -> Result<(), QubError> {
+        let name = self.ident()?;
+        if self.gates.contains_key(&name)
+            || STANDARD_GATES
+                .iter()
+                .any(|(standard, ..)| *standard == name)
+        {
+            return Err(self.error(format!("gate {name} is already defined")));
+        }
+
+        let mut params = Vec::new();
+        if self.eat("(") && !self.eat(")") {
+            params = self.identifiers()?;
+            self.expect(")")?;
+        }
+        let qubits = self.identifiers()?;
+        self.expect("{")?;
+
+        let mut body = Vec::new();
+        while !self.eat("}") {
+            let gate = self.ident()?;
+            if gate != "barrier"
+                && !self.gates.contains_key(&gate)
+                && !STANDARD_GATES
+                    .iter()
+                    .any(|(standard, ..)| *standard == gate)
+            {
+                return Err(self.error(format!("unknown gate {gate}")));
+            }
+
+            let mut exprs = Vec::new();
+            if self.eat("(") {
+                exprs = self.expressions(&params)?;
+                self.expect(")")?;
+            }
+            let arguments = self
+                .identifiers()?
+                .iter()
+                .map(|argument| {
+                    qubits
+                        .iter()
+                        .position(|qubit| qubit == argument)
+                        .ok_or_else(|| self.error(format!("unknown qubit argument {argument}")))
+                })
+                .collect::<Result<_, _>>()?;
+            self.expect(";")?;
+            body.push((gate, exprs, arguments));
+        }
+
+        self.gates.insert(
+            name,
+            GateDefinition {
+                params,
+                n_qubits: qubits.len(),
+                body,
+            },
+        );
+        Ok(())
+    }
+
+    fn identifiers(&mut self) -> Result<Vec<String>, QubError> {
+        let mut identifiers = vec![self.ident()?];
+        while self.eat(",") {
+            identifiers.push(self.ident()?);
+        }
+        Ok(identifiers)
+    }
+
+    fn expressions(&mut self, params: &[String]) -> Result<Vec<Expr>, QubError> {
+        let mut exprs = vec![self.expression(params)?];
+        while self.eat(",") {
+            exprs.push(self.expression(params)?);
+        }
+        Ok(exprs)
+    }
+
+    fn expression(&mut self, params: &[String]) -> Result<Expr, QubError> {
+        let mut expr = self.term(params)?;
+        loop {
+            let op = if self.eat("+") {
+                "+"
+            } else if self.eat("-") {
+                "-"
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term(params)?));
+        }
+    }
+
+    fn term(&mut self, params: &[String]) -> Result<Expr, QubError> {
+        let mut expr = self.unary(params)?;
+        loop {
+            let op = if self.eat("*") {
+                "*"
+            } else if self.eat("/") {
+                "/"
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary(params)?));
+        }
+    }
+
+    fn unary(&mut self, params: &[String]) -> Result<Expr, QubError> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary(params)?)));
+        }
+
+        let base = self.primary(params)?;
+        if self.eat("^") {
+            Ok(Expr::Binary(
+                "^",
+                Box::new(base),
+                Box::new(self.unary(params)?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self, params: &[String]) -> Result<Expr, QubError> {
+        match self.next()? {
+            Token::Int(value) => Ok(Expr::Number(value as f64)),
+            Token::Real(value) => Ok(Expr::Number(value)),
+            Token::Ident(name) if name == "pi" => Ok(Expr::Pi),
+            Token::Ident(name) if FUNCTIONS.contains(&name.as_str()) => {
+                self.expect("(")?;
+                let argument = self.expression(params)?;
+                self.expect(")")?;
+                Ok(Expr::Call(name, Box::new(argument)))
+            }
+            Token::Ident(name) if params.contains(&name) => Ok(Expr::Param(name)),
+            Token::Ident(name) => {
+                self.position -= 1;
+                Err(self.error(format!("unknown parameter {name}")))
+            }
+            Token::Symbol("(") => {
+                let expr = self.expression(params)?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.error("expected an expression"))
+            }
+        }
+    }
+}
+
+/// The definition of the ZZ coupling gate, which `stdgates.inc` lacks.
+const RZZ_DEFINITION: &str = "gate rzz(theta) a, b { cx a, b; rz(theta) b; cx a, b; }";
+
+/// The definition of the XX coupling gate, which `stdgates.inc` lacks.
+const RXX_DEFINITION: &str = "gate rxx(theta) a, b { h a; h b; rzz(theta) a, b; h a; h b; }";
+
+/// Get the global phase γ and the angles θ, φ and λ of a single-qubit gate,
+/// such that it equals e^(iγ) U3(θ, φ, λ).
+fn u3_angles<T: Float>(matrix: &Array2<Complex<T>>, tolerance: T) -> (T, T, T, T) {
+    let (a, b, c, d) = (
+        matrix[[0, 0]],
+        matrix[[0, 1]],
+        matrix[[1, 0]],
+        matrix[[1, 1]],
+    );
+    let theta = (T::one() + T::one()) * c.norm().atan2(a.norm());
+
+    if c.norm() <= tolerance {
+        let gamma = a.arg();
+        (gamma, theta, T::zero(), d.arg() - gamma)
+    } else if a.norm() <= tolerance {
+        let gamma = (-b).arg();
+        (gamma, theta, c.arg() - gamma, T::zero())
+    } else {
+        let gamma = a.arg();
+        (gamma, theta, c.arg() - gamma, (-b).arg() - gamma)
+    }
+}
+
+/// A single-qubit gate applied to a target qubit
+/// only when each control qubit holds the given value.
+#[derive(Debug, Clone, PartialEq)]
+struct ControlledGate<T: Float> {
+    controls: Vec<(usize, bool)>,
+    target: usize,
+    matrix: Array2<Complex<T>>,
+}
+
+/// Decompose a gate acting on the given qubits into controlled single-qubit gates,
+/// in the order they are applied.
+/// Each factor is a two-level unitary mixing two basis states, which are visited
+/// in Gray code order so that consecutive states differ in a single qubit.
+fn two_level_decomposition<T: Float + 'static>(
+    gate: &QuGate<T>,
+    qubits: &[usize],
+    tolerance: T,
+) -> Vec<ControlledGate<T>> {
+    let gray = |index: usize| index ^ (index >> 1);
+    let one = Complex::new(T::one(), T::zero());
+    let zero = Complex::new(T::zero(), T::zero());
+    let dim = gate.matrix().nrows();
+
+    // Reduce the matrix to the identity with two-level unitaries Vₘ...V₁ U = I,
+    // so that U = V₁†...Vₘ†.
+    let mut matrix = gate.matrix().clone();
+    let mut steps = Vec::new();
+    for position in 0..dim {
+        let column = gray(position);
+        for row in (position + 1..dim).rev() {
+            let (a, b) = (gray(row - 1), gray(row));
+            let (x, y) = (matrix[[a, column]], matrix[[b, column]]);
+            let norm = (x.norm_sqr() + y.norm_sqr()).sqrt();
+            // The last step of a column also makes its diagonal entry real and positive.
+            let phase_done = row != position + 1 || (x - x.norm()).norm() <= tolerance;
+            if norm <= tolerance || (y.norm() <= tolerance && phase_done) {
+                continue;
+            }
+
+            let step = array![[x.conj() / norm, y.conj() / norm], [-y / norm, x / norm]];
+            for index in 0..dim {
+                let (top, bottom) = (matrix[[a, index]], matrix[[b, index]]);
+                matrix[[a, index]] = step[[0, 0]] * top + step[[0, 1]] * bottom;
+                matrix[[b, index]] = step[[1, 0]] * top + step[[1, 1]] * bottom;
+            }
+            steps.push((a, b, step));
+        }
+    }
+    let last = gray(dim - 1);
+    let phase = matrix[[last, last]];
+    if (phase - one).norm() > tolerance {
+        steps.push((
+            gray(dim - 2),
+            last,
+            array![[one, zero], [zero, phase.conj()]],
+        ));
+    }
+
+    let n_qubits = qubits.len();
+    let mask = |qubit: usize| 1 << (n_qubits - 1 - qubit);
+    steps
+        .into_iter()
+        .rev()
+        .map(|(a, b, step)| {
+            let target = (0..n_qubits).find(|&qubit| (a ^ b) == mask(qubit)).unwrap();
+            let matrix = step.t().mapv(|x| x.conj());
+            // The factor acts on (|a⟩, |b⟩), so flip it when |a⟩ has the target set.
+            let matrix = if a & mask(target) == 0 {
+                matrix
+            } else {
+                array![
+                    [matrix[[1, 1]], matrix[[1, 0]]],
+                    [matrix[[0, 1]], matrix[[0, 0]]]
+                ]
+            };
+
+            ControlledGate {
+                controls: (0..n_qubits)
+                    .filter(|&qubit| qubit != target)
+                    .map(|qubit| (qubits[qubit], a & mask(qubit) != 0))
+                    .collect(),
+                target: qubits[target],
+                matrix,
+            }
+        })
+        .collect()
+}
+
+/// Format the control modifiers of a gate, one per run of controls
+/// on the same value, e.g. `ctrl(2) @ negctrl @ `.
+fn modifiers(values: impl IntoIterator<Item = bool>) -> String {
+    let values: Vec<bool> = values.into_iter().collect();
+    let mut text = String::new();
+    for run in values.chunk_by(|a, b| a == b) {
+        let name = if run[0] { "ctrl" } else { "negctrl" };
+        match run.len() {
+            1 => write!(text, "{name} @ "),
+            n => write!(text, "{name}({n}) @ "),
+        }
+        .unwrap();
+    }
+    text
+}
+
+/// Format a number in its shortest exact decimal form.
+pub(crate) fn format_number<T: Float>(value: T) -> String {
+    match value.to_f64().unwrap() {
+        // Avoid printing negative zero.
+        0.0 => "0".to_string(),
+        value => value.to_string(),
+    }
+}
+
+fn format_params<T: Float>(params: &[T]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+    let params: Vec<String> = params.iter().map(|&param| format_number(param)).collect();
+    format!("({})", params.join(", "))
+}
+
+fn format_qubits(qubits: impl IntoIterator<Item = usize>) -> String {
+    let qubits: Vec<String> = qubits
+        .into_iter()
+        .map(|qubit| format!("q[{qubit}]"))
+        .collect();
+    qubits.join(", ")
+}
+
+/// Get the OpenQASM 3 statements of a gate, emitting built-in gates by name
+/// and any other gate as controlled U gates with their global phase.
+fn gate_statements<T: Float + 'static>(
+    name: &str,
+    params: &[T],
+    gate: &QuGate<T>,
+    controls: &[usize],
+    targets: &[usize],
+    definitions: &mut Vec<&'static str>,
+) -> Vec<String> {
+    let tolerance = T::epsilon().sqrt();
+    let qubits = || controls.iter().chain(targets).copied();
+
+    if named_gate(name, params).is_some_and(|named| named.approx_eq(gate, tolerance)) {
+        let needed: &[&str] = match name {
+            "rxx" => &[RZZ_DEFINITION, RXX_DEFINITION],
+            "rzz" => &[RZZ_DEFINITION],
+            _ => &[],
+        };
+        for definition in needed {
+            if !definitions.contains(definition) {
+                definitions.push(definition);
+            }
+        }
+
+        let qasm_name = match (name, controls.len()) {
+            ("x", 1) => "cx".to_string(),
+            ("x", 2) => "ccx".to_string(),
+            ("y", 1) => "cy".to_string(),
+            ("z", 1) => "cz".to_string(),
+            ("h", 1) => "ch".to_string(),
+            ("p", 1) => "cp".to_string(),
+            ("rx", 1) => "crx".to_string(),
+            ("ry", 1) => "cry".to_string(),
+            ("rz", 1) => "crz".to_string(),
+            ("swap", 1) => "cswap".to_string(),
+            (name, n_controls) => {
+                let base = match name {
+                    "u3" => "U",
+                    "sxdg" => "inv @ sx",
+                    name => name,
+                };
+                format!("{}{base}", modifiers(vec![true; n_controls]))
+            }
+        };
+        return vec![format!(
+            "{qasm_name}{} {};",
+            format_params(params),
+            format_qubits(qubits())
+        )];
+    }
+
+    let factors = if targets.len() == 1 {
+        vec![ControlledGate {
+            controls: Vec::new(),
+            target: targets[0],
+            matrix: gate.matrix().clone(),
+        }]
+    } else {
+        two_level_decomposition(gate, targets, tolerance)
+    };
+
+    let mut statements = Vec::new();
+    for factor in factors {
+        let controls: Vec<(usize, bool)> = controls
+            .iter()
+            .map(|&control| (control, true))
+            .chain(factor.controls)
+            .collect();
+        let modifiers = modifiers(controls.iter().map(|&(_, value)| value));
+        let control_qubits = || controls.iter().map(|&(qubit, _)| qubit);
+        let (gamma, theta, phi, lambda) = u3_angles(&factor.matrix, tolerance);
+
+        if gamma.abs() > tolerance {
+            let gamma = format_params(&[gamma]);
+            statements.push(if controls.is_empty() {
+                format!("gphase{gamma};")
+            } else {
+                format!(
+                    "{modifiers}gphase{gamma} {};",
+                    format_qubits(control_qubits())
+                )
+            });
+        }
+        statements.push(format!(
+            "{modifiers}U{} {};",
+            format_params(&[theta, phi, lambda]),
+            format_qubits(control_qubits().chain([factor.target]))
+        ));
+    }
+    statements
+}
+
+/// Get the OpenQASM 3 statements of an operation,
+/// recording the gate definitions they need.
+fn statements<T: Float + 'static>(
+    operation: &Operation<T>,
+    n_clbits: usize,
+    definitions: &mut Vec<&'static str>,
+) -> Vec<String> {
+    match operation {
+        Operation::Gate {
+            name,
+            params,
+            gate,
+            controls,
+            targets,
+        } => gate_statements(name, params, gate, controls, targets, definitions),
+        Operation::Measure { qubit, clbit } => vec![format!("c[{clbit}] = measure q[{qubit}];")],
+        Operation::Barrier(qubits) if qubits.is_empty() => vec!["barrier;".to_string()],
+        Operation::Barrier(qubits) => {
+            vec![format!(
+                "barrier {};",
+                format_qubits(qubits.iter().copied())
+            )]
+        }
+        Operation::Conditional {
+            clbits,
+            value,
+            operation,
+        } => {
+            let condition = if clbits.is_empty() {
+                "true".to_string()
+            } else if clbits.iter().copied().eq(0..n_clbits) {
+                format!("c == {value}")
+            } else {
+                let bits: Vec<String> = clbits
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, clbit)| {
+                        if bit < usize::BITS as usize && (value >> bit) & 1 == 1 {
+                            format!("c[{clbit}]")
+                        } else {
+                            format!("!c[{clbit}]")
+                        }
+                    })
+                    .collect();
+                bits.join(" && ")
+            };
+
+            let inner = statements(operation, n_clbits, definitions);
+            if let [statement] = inner.as_slice() {
+                vec![format!("if ({condition}) {statement}")]
+            } else {
+                iter::once(format!("if ({condition}) {{"))
+                    .chain(inner.iter().map(|statement| format!("    {statement}")))
+                    .chain(iter::once("}".to_string()))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl<T: Float + 'static> QuCircuit<T> {
+    /// Parse an OpenQASM 2.0 program into a circuit.
+    /// Registers are laid out in declaration order,
+    /// the gates of `qelib1.inc` are always available,
+    /// and gates defined in the program are expanded into their bodies.
+    /// Reset and opaque gates are not supported, and neither are the `cu`, `rccx`
+    /// and `rc3x` gates of `qelib1.inc` or OpenQASM 3 programs
+    /// such as those written by `to_qasm3`.
+    pub fn from_qasm(source: &str) -> Result<Self, QubError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            qregs: HashMap::new(),
+            cregs: HashMap::new(),
+            n_qubits: 0,
+            n_clbits: 0,
+            gates: HashMap::new(),
+            operations: Vec::new(),
+        };
+        parser.program()?;
+
+        let mut circuit = Self::with_clbits(parser.n_qubits, parser.n_clbits);
+        for (line, operation) in parser.operations {
+            circuit
+                .try_push(operation)
+                .map_err(|error| QubError::Parse {
+                    line,
+                    message: error.to_string(),
+                })?;
+        }
+        Ok(circuit)
+    }
+
+    /// Serialise the circuit to an OpenQASM 3 program over a qubit register `q`
+    /// and a bit register `c`.
+    /// Built-in gates are emitted by name with control modifiers as needed,
+    /// and any other gate is decomposed into controlled U gates.
+    /// The program uses OpenQASM 3 syntax, so it cannot be read back by `from_qasm`.
+    pub fn to_qasm3(&self) -> String {
+        let mut definitions = Vec::new();
+        let body: Vec<String> = self
+            .operations()
+            .iter()
+            .flat_map(|operation| statements(operation, self.n_clbits(), &mut definitions))
+            .collect();
+
+        let mut program = String::from("OPENQASM 3.0;\ninclude \"stdgates.inc\";\n");
+        for line in definitions
+            .into_iter()
+            .map(str::to_string)
+            .chain(iter::once(format!("qubit[{}] q;", self.n_qubits())))
+            .chain((self.n_clbits() > 0).then(|| format!("bit[{}] c;", self.n_clbits())))
+            .chain(body)
+        {
+            program.push_str(&line);
+            program.push('\n');
+        }
+        program
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quregister::QuRegister;
+
+    #[test]
+    fn bell_pair() {
+        let circuit = QuCircuit::<f64>::from_qasm(
+            r#"
+            OPENQASM 2.0;
+            include "qelib1.inc";
+            // Prepare a Bell pair and measure it.
+            qreg q[2];
+            creg c[2];
+            h q[0];
+            cx q[0], q[1];
+            barrier q;
+            measure q -> c;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            circuit,
+            QuCircuit::new(2).h(0).cx(0, 1).barrier().measure_all()
+        );
+    }
+
+    #[test]
+    fn parameters_and_definitions() {
+        let circuit = QuCircuit::<f64>::from_qasm(
+            "OPENQASM 2.0;
+            gate bell(theta) a, b { ry(theta * 2 - pi / 2) a; CX a, b; }
+            qreg q[4];
+            creg c[4];
+            bell(pi / 4) q[0], q[1];
+            u2(0, pi) q[2];
+            rz(-1.5e-1) q[3];",
+        )
+        .unwrap();
+
+        let expected = QuCircuit::new(4)
+            .ry(0.0, 0)
+            .cx(0, 1)
+            .u3(std::f64::consts::FRAC_PI_2, 0.0, std::f64::consts::PI, 2)
+            .rz(-0.15, 3);
+        assert_eq!(circuit.operations().len(), expected.operations().len());
+        assert!(circuit
+            .run()
+            .state()
+            .approx_eq(expected.run().state(), 1e-12));
+    }
+
+    #[test]
+    fn standard_gates() {
+        let circuit = QuCircuit::<f64>::from_qasm(
+            "OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[5];
+            u0(1) q[0];
+            csx q[0], q[1];
+            c3x q[0], q[1], q[2], q[3];
+            c3sqrtx q[1], q[2], q[3], q[4];
+            c4x q[0], q[1], q[2], q[3], q[4];",
+        )
+        .unwrap();
+
+        let expected = QuCircuit::with_clbits(5, 0)
+            .gate("id", QuGate::identity(1), &[0])
+            .controlled_gate("sx", Vec::new(), QuGate::sx(), &[0], &[1])
+            .controlled_gate("x", Vec::new(), QuGate::pauli_x(), &[0, 1, 2], &[3])
+            .controlled_gate("sx", Vec::new(), QuGate::sx(), &[1, 2, 3], &[4])
+            .controlled_gate("x", Vec::new(), QuGate::pauli_x(), &[0, 1, 2, 3], &[4]);
+        assert_eq!(circuit, expected);
+    }
+
+    #[test]
+    fn conditional() {
+        let circuit = QuCircuit::<f64>::from_qasm(
+            "OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[2];
+            creg c[1];
+            x q[0];
+            measure q[0] -> c[0];
+            if (c == 1) x q;",
+        )
+        .unwrap();
+
+        assert_eq!(circuit.run().state(), &QuRegister::basis(2, 0b01));
+    }
+
+    #[test]
+    fn export() {
+        let circuit = QuCircuit::<f64>::with_clbits(3, 2)
+            .h(0)
+            .cx(0, 1)
+            .ccx(0, 1, 2)
+            .gate("sxdg", QuGate::sx_dagger(), &[2])
+            .controlled_gate("h", Vec::new(), QuGate::hadamard(), &[0, 1], &[2])
+            .rz(0.5, 1)
+            .controlled_gate("rxx", vec![0.25], QuGate::rxx(0.25), &[], &[0, 2])
+            .measure_into(2, 1)
+            .conditional(&[1], 1, Operation::Barrier(vec![0, 1]));
+
+        assert_eq!(
+            circuit.to_qasm3(),
+            "OPENQASM 3.0;
+include \"stdgates.inc\";
+gate rzz(theta) a, b { cx a, b; rz(theta) b; cx a, b; }
+gate rxx(theta) a, b { h a; h b; rzz(theta) a, b; h a; h b; }
+qubit[3] q;
+bit[2] c;
+h q[0];
+cx q[0], q[1];
+ccx q[0], q[1], q[2];
+inv @ sx q[2];
+ctrl(2) @ h q[0], q[1], q[2];
+rz(0.5) q[1];
+rxx(0.25) q[0], q[2];
+c[1] = measure q[2];
+if (c[1]) barrier q[0], q[1];
+"
+        );
+    }
+
+    #[test]
+    fn decompose() {
+        let tolerance = 1e-12;
+        let gate = QuGate::<f64>::s().tensor(&QuGate::ry(0.3));
+        let (gamma, theta, phi, lambda) = u3_angles(QuGate::<f64>::hadamard().matrix(), tolerance);
+        assert!(
+            QuGate::new(QuGate::u3(theta, phi, lambda).matrix() * Complex::cis(gamma))
+                .approx_eq(&QuGate::hadamard(), tolerance)
+        );
+
+        // Rebuild each gate from its factors, conjugating negative controls with X gates.
+        for gate in [
+            QuGate::cnot().matrix().dot(gate.matrix()),
+            QuGate::fredkin()
+                .matrix()
+                .dot(QuGate::hadamard().tensor(&QuGate::rxx(0.7)).matrix()),
+        ] {
+            let gate = QuGate::new(gate);
+            let qubits: Vec<usize> = (0..gate.n_qubits()).collect();
+            let factors = two_level_decomposition(&gate, &qubits, tolerance);
+
+            let columns: Vec<QuRegister<f64>> = (0..1 << gate.n_qubits())
+                .map(|index| {
+                    factors.iter().fold(
+                        QuRegister::basis(gate.n_qubits(), index),
+                        |state, factor| {
+                            let flip = |state: QuRegister<f64>| {
+                                factor
+                                    .controls
+                                    .iter()
+                                    .filter(|(_, value)| !value)
+                                    .fold(state, |state, &(qubit, _)| {
+                                        QuGate::pauli_x().apply_to(&state, qubit)
+                                    })
+                            };
+                            let qubits: Vec<usize> = factor
+                                .controls
+                                .iter()
+                                .map(|&(qubit, _)| qubit)
+                                .chain([factor.target])
+                                .collect();
+                            let controlled = QuGate::new(factor.matrix.clone())
+                                .controlled(factor.controls.len());
+                            flip(controlled.apply_to_qubits(&flip(state), &qubits))
+                        },
+                    )
+                })
+                .collect();
+            let rebuilt = Array2::from_shape_fn(gate.matrix().dim(), |(row, column)| {
+                columns[column].get_state()[row]
+            });
+
+            assert!(QuGate::new(rebuilt).approx_eq(&gate, 1e-9));
+        }
+    }
+
+    #[test]
+    fn export_matrix() {
+        let circuit = QuCircuit::<f64>::new(2)
+            .gate("custom", QuGate::hadamard(), &[0])
+            .controlled_gate("s", Vec::new(), QuGate::t(), &[0], &[1]);
+        let program = circuit.to_qasm3();
+
+        assert!(program.contains("\nU(1.5707963267948966, 0, -3.141592653589793) q[0];\n"));
+        assert!(program.contains("\nctrl @ U(0, 0, 0.7853981633974483) q[0], q[1];\n"));
+        assert!(!program.contains("gphase"));
+    }
+
+    #[test]
+    fn errors() {
+        let parse = QuCircuit::<f64>::from_qasm;
+
+        assert_eq!(
+            parse("qreg q[1];\nfoo q[0];"),
+            Err(QubError::Parse {
+                line: 2,
+                message: "unknown gate foo".to_string()
+            })
+        );
+        assert_eq!(
+            parse("qreg q[2];\n\ncx q[1], q[1];"),
+            Err(QubError::Parse {
+                line: 3,
+                message: "qubits must be distinct: 1 is repeated".to_string()
+            })
+        );
+        assert!(parse("qreg q[1];\nrx q[0];").is_err());
+        assert!(parse("qreg q[1];\nh q[1];").is_err());
+        assert!(parse("include \"other.inc\";").is_err());
+        assert_eq!(
+            parse("qreg q[2];\ncreg c[2];\nif(c==5) x q[1];"),
+            Err(QubError::Parse {
+                line: 3,
+                message: "value 5 does not fit in 2 bits".to_string()
+            })
+        );
+        assert_eq!(
+            parse("qreg q[2];\ncreg c[2];\nif(c==0) measure q -> c;\nx q[0];"),
+            Err(QubError::Parse {
+                line: 3,
+                message: "measurements into the register read by if cannot be broadcast"
+                    .to_string()
+            })
+        );
+        assert!(parse("qreg q[2];\ncreg c[2];\ncreg d[2];\nif(c==0) measure q -> d;").is_ok());
+    }
+}
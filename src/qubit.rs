@@ -2,10 +2,19 @@ use ndarray::prelude::*;
 use num::{complex::Complex, Float};
 use rand::prelude::*;
 
+use crate::{
+    basis::Basis,
+    compare,
+    counts::Counts,
+    error::QubError,
+    qugate::QuGate,
+    quregister::{sample_index, QuRegister},
+};
+
 /// A qubit is a quantum bit.
 /// It is a two-level quantum system that can be in a superposition of the |0⟩ and |1⟩ states.
 /// The state of a qubit is described by a complex vector of size 2.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Qubit<T: Float> {
     /// The state of the qubit, represented as a complex vector of size 2.
     pub(crate) state: Array1<Complex<T>>,
@@ -20,6 +29,36 @@ impl<T: Float> Qubit<T> {
         }
     }
 
+    /// Create a new qubit with alpha amplitude to the |0⟩
+    /// and beta amplitude to the |1⟩ state,
+    /// failing if both amplitudes are zero.
+    pub fn try_new(alpha: Complex<T>, beta: Complex<T>) -> Result<Self, QubError> {
+        if alpha.norm_sqr() + beta.norm_sqr() == T::zero() {
+            return Err(QubError::ZeroNorm);
+        }
+
+        Ok(Self::new(alpha, beta))
+    }
+
+    /// Create a new qubit with amplitudes proportional to alpha and beta,
+    /// rescaled so that the state has unit norm.
+    ///
+    /// # Panics
+    /// Panics if both amplitudes are zero.
+    pub fn normalized(alpha: Complex<T>, beta: Complex<T>) -> Self {
+        let norm = (alpha.norm_sqr() + beta.norm_sqr()).sqrt();
+        assert!(norm > T::zero(), "{}", QubError::ZeroNorm);
+
+        Self::new(alpha / norm, beta / norm)
+    }
+
+    /// Create a new qubit from its polar and azimuthal angles on the Bloch sphere,
+    /// as cos(θ/2)|0⟩ + e^{iφ} sin(θ/2)|1⟩.
+    pub fn from_bloch(theta: T, phi: T) -> Self {
+        let (sin, cos) = (theta / (T::one() + T::one())).sin_cos();
+        Self::new(Complex::new(cos, T::zero()), Complex::cis(phi) * sin)
+    }
+
     /// Create a new qubit in the |0⟩ state.
     pub fn zero() -> Self {
         Self::new(
@@ -51,6 +90,11 @@ impl<T: Float> Qubit<T> {
         self.state[1]
     }
 
+    /// Get the inner product ⟨self|other⟩ of this qubit with another.
+    pub fn inner(&self, other: &Self) -> Complex<T> {
+        self.alpha().conj() * other.alpha() + self.beta().conj() * other.beta()
+    }
+
     /// Get the probabilities of the qubit being in the |0⟩ and |1⟩ states.
     pub fn probabilities(&self) -> (T, T) {
         let p = self.state.mapv(|x| x.norm_sqr());
@@ -68,20 +112,239 @@ impl<T: Float> Qubit<T> {
         self.probabilities().1
     }
 
+    /// Get the Cartesian coordinates (x, y, z) of the qubit on the Bloch sphere,
+    /// i.e. the expectation values of the Pauli-X, Y and Z operators.
+    pub fn bloch_vector(&self) -> (T, T, T) {
+        let two = T::one() + T::one();
+        let coherence = self.alpha().conj() * self.beta();
+        let (p0, p1) = self.probabilities();
+
+        (two * coherence.re, two * coherence.im, p0 - p1)
+    }
+
+    /// Get the polar and azimuthal angles (θ, φ) of the qubit on the Bloch sphere,
+    /// ignoring its global phase.
+    /// φ is in (-π, π] and is zero when the qubit is at a pole.
+    pub fn bloch_angles(&self) -> (T, T) {
+        let theta = (T::one() + T::one()) * self.beta().norm().atan2(self.alpha().norm());
+        let (x, y, _) = self.bloch_vector();
+
+        (theta, y.atan2(x))
+    }
+
     /// Validate the qubit state.
     pub fn validate(&self) -> bool {
         let (p0, p1) = self.probabilities();
         p0 + p1 == T::one()
     }
 
+    /// Validate the qubit state, allowing its norm to differ from one
+    /// by at most the given tolerance to absorb floating point errors.
+    pub fn validate_with(&self, tolerance: T) -> bool {
+        let (p0, p1) = self.probabilities();
+        (p0 + p1 - T::one()).abs() <= tolerance
+    }
+
+    /// Check whether this qubit equals another within the given tolerance
+    /// on each amplitude.
+    pub fn approx_eq(&self, other: &Self, tolerance: T) -> bool {
+        compare::approx_eq(&self.state, &other.state, tolerance)
+    }
+
+    /// Check whether this qubit equals another within the given tolerance
+    /// up to a global phase, which has no physical effect,
+    /// e.g. -|0⟩ and |0⟩ are equal up to global phase.
+    pub fn eq_up_to_global_phase(&self, other: &Self, tolerance: T) -> bool {
+        compare::eq_up_to_global_phase(&self.state, &other.state, tolerance)
+    }
+
     /// Measure the qubit in the computational basis.
     /// Collapse the qubit to either the |0⟩ or |1⟩ state.
     pub fn measure(&self) -> Self {
-        if random::<f64>() < self.zero_probability().to_f64().unwrap() {
-            Self::zero()
+        self.measure_with(&mut thread_rng())
+    }
+
+    /// Measure the qubit in the computational basis,
+    /// drawing randomness from the given random number generator.
+    pub fn measure_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Self {
+        self.observe_with(rng).state
+    }
+
+    /// Measure the qubit in the computational basis.
+    /// Return the observed bit, its probability and the collapsed qubit.
+    pub fn observe(&self) -> Measurement<T> {
+        self.observe_with(&mut thread_rng())
+    }
+
+    /// Measure the qubit in the computational basis,
+    /// drawing randomness from the given random number generator.
+    /// Return the observed bit, its probability and the collapsed qubit.
+    ///
+    /// # Panics
+    /// Panics if the qubit has zero norm.
+    pub fn observe_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Measurement<T> {
+        self.observe_in_with(&Basis::Z, rng)
+    }
+
+    /// Measure the qubit in the computational basis,
+    /// drawing randomness from the given random number generator,
+    /// failing if the qubit has zero norm.
+    pub fn try_observe_with<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<Measurement<T>, QubError> {
+        self.try_observe_in_with(&Basis::Z, rng)
+    }
+
+    /// Measure the qubit in the given basis.
+    /// Return which basis state was observed, its probability
+    /// and the qubit collapsed to that basis state.
+    ///
+    /// # Panics
+    /// Panics if the qubit has zero norm.
+    pub fn observe_in(&self, basis: &Basis<T>) -> Measurement<T> {
+        self.observe_in_with(basis, &mut thread_rng())
+    }
+
+    /// Measure the qubit in the given basis,
+    /// drawing randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if the qubit has zero norm.
+    pub fn observe_in_with<R: Rng + ?Sized>(
+        &self,
+        basis: &Basis<T>,
+        rng: &mut R,
+    ) -> Measurement<T> {
+        self.try_observe_in_with(basis, rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Measure the qubit in the given basis,
+    /// drawing randomness from the given random number generator,
+    /// failing if the qubit has zero norm.
+    pub fn try_observe_in_with<R: Rng + ?Sized>(
+        &self,
+        basis: &Basis<T>,
+        rng: &mut R,
+    ) -> Result<Measurement<T>, QubError> {
+        let (zero, one) = basis.states();
+        let p0 = zero.inner(self).norm_sqr();
+        let p1 = one.inner(self).norm_sqr();
+
+        let measurement = if sample_index(&[p0, p1], rng)? == 0 {
+            Measurement {
+                outcome: false,
+                probability: p0,
+                state: zero,
+            }
         } else {
-            Self::one()
-        }
+            Measurement {
+                outcome: true,
+                probability: p1,
+                state: one,
+            }
+        };
+
+        Ok(measurement)
+    }
+
+    /// Measure the qubit in the computational basis, collapsing it in place.
+    /// Return the observed bit.
+    pub fn collapse(&mut self) -> bool {
+        self.collapse_with(&mut thread_rng())
+    }
+
+    /// Measure the qubit in the computational basis, collapsing it in place,
+    /// drawing randomness from the given random number generator.
+    /// Return the observed bit.
+    pub fn collapse_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> bool {
+        let measurement = self.observe_with(rng);
+        *self = measurement.state;
+        measurement.outcome
+    }
+
+    /// Sample the given number of measurement shots in the computational basis,
+    /// without re-simulating the qubit for each shot.
+    ///
+    /// # Panics
+    /// Panics if the qubit has zero norm.
+    pub fn sample<R: Rng + ?Sized>(&self, shots: usize, rng: &mut R) -> Counts {
+        self.try_sample(shots, rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Sample the given number of measurement shots in the computational basis,
+    /// without re-simulating the qubit for each shot,
+    /// failing if the qubit has zero norm.
+    pub fn try_sample<R: Rng + ?Sized>(
+        &self,
+        shots: usize,
+        rng: &mut R,
+    ) -> Result<Counts, QubError> {
+        let (p0, p1) = self.probabilities();
+        Counts::sample(1, [p0, p1], shots, rng)
+    }
+
+    /// Combine this qubit with another into a joint two-qubit state |self⟩ ⊗ |other⟩.
+    pub fn tensor(&self, other: &Self) -> QuRegister<T> {
+        QuRegister::from(self.clone()).tensor(&QuRegister::from(other.clone()))
+    }
+}
+
+impl<T: Float + 'static> Qubit<T> {
+    /// Get the expectation value ⟨ψ|O|ψ⟩ of a Hermitian single-qubit observable.
+    ///
+    /// # Panics
+    /// Panics if the observable is not a single-qubit operator.
+    pub fn expectation(&self, observable: &QuGate<T>) -> T {
+        self.inner(&observable.apply(self)).re
+    }
+
+    /// Get the expectation value ⟨X⟩ of the Pauli-X observable.
+    pub fn expectation_x(&self) -> T {
+        self.expectation(&QuGate::pauli_x())
+    }
+
+    /// Get the expectation value ⟨Y⟩ of the Pauli-Y observable.
+    pub fn expectation_y(&self) -> T {
+        self.expectation(&QuGate::pauli_y())
+    }
+
+    /// Get the expectation value ⟨Z⟩ of the Pauli-Z observable.
+    pub fn expectation_z(&self) -> T {
+        self.expectation(&QuGate::pauli_z())
+    }
+}
+
+/// The result of measuring a qubit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement<T: Float> {
+    outcome: bool,
+    probability: T,
+    state: Qubit<T>,
+}
+
+impl<T: Float> Measurement<T> {
+    /// Get the observed bit, `false` for |0⟩ and `true` for |1⟩,
+    /// or respectively the first and second state of the measurement basis.
+    pub fn outcome(&self) -> bool {
+        self.outcome
+    }
+
+    /// Get the probability of the observed outcome before the measurement.
+    pub fn probability(&self) -> T {
+        self.probability
+    }
+
+    /// Get the qubit after the measurement.
+    pub fn state(&self) -> &Qubit<T> {
+        &self.state
+    }
+
+    /// Take the qubit after the measurement.
+    pub fn into_state(self) -> Qubit<T> {
+        self.state
     }
 }
 
@@ -91,6 +354,30 @@ impl<T: Float> Default for Qubit<T> {
     }
 }
 
+#[cfg(feature = "approx")]
+impl<T: Float + approx::AbsDiffEq<Epsilon = T>> approx::AbsDiffEq for Qubit<T> {
+    type Epsilon = T;
+
+    fn default_epsilon() -> T {
+        T::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: T) -> bool {
+        self.approx_eq(other, epsilon)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl<T: Float + approx::RelativeEq<Epsilon = T>> approx::RelativeEq for Qubit<T> {
+    fn default_max_relative() -> T {
+        T::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: T, max_relative: T) -> bool {
+        compare::relative_eq(&self.state, &other.state, epsilon, max_relative)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +392,92 @@ mod tests {
         assert_eq!(qubit.beta(), 0.5 + 0.0 * i);
     }
 
+    #[test]
+    fn validate_with() {
+        let h_gate = QuGate::hadamard();
+        let qubit = h_gate.apply(&h_gate.apply(&QuGate::rx(0.3).apply(&Qubit::<f64>::zero())));
+
+        assert!(!qubit.validate());
+        assert!(qubit.validate_with(1e-12));
+        assert!(!Qubit::new(1.0 + 0.0 * i, 1.0 + 0.0 * i).validate_with(1e-12));
+    }
+
+    #[test]
+    fn normalized() {
+        let qubit = Qubit::normalized(3.0 + 0.0 * i, 0.0 + 4.0 * i);
+        assert_eq!(qubit, Qubit::new(0.6 + 0.0 * i, 0.0 + 0.8 * i));
+        assert_eq!(Qubit::try_new(0.0 * i, 0.0 * i), Err(QubError::ZeroNorm));
+        assert!(Qubit::try_new(1.0 + 0.0 * i, 0.0 * i).is_ok());
+    }
+
+    #[test]
+    fn approx_eq() {
+        let h_gate = QuGate::hadamard();
+        let qubit = h_gate.apply(&h_gate.apply(&Qubit::<f64>::zero()));
+        let minus_zero = Qubit::new(-1.0 + 0.0 * i, 0.0 * i);
+
+        assert!(qubit.approx_eq(&Qubit::zero(), 1e-12));
+        assert!(!minus_zero.approx_eq(&Qubit::zero(), 1e-12));
+        assert!(minus_zero.eq_up_to_global_phase(&Qubit::zero(), 1e-12));
+        assert!(!Qubit::<f64>::one().eq_up_to_global_phase(&Qubit::zero(), 1e-12));
+    }
+
+    #[cfg(feature = "approx")]
+    #[test]
+    fn approx_traits() {
+        let h_gate = QuGate::hadamard();
+        let qubit = h_gate.apply(&h_gate.apply(&Qubit::<f64>::zero()));
+
+        approx::assert_abs_diff_eq!(qubit, Qubit::zero(), epsilon = 1e-12);
+        approx::assert_relative_eq!(qubit, Qubit::zero(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn observe_in() {
+        let plus = QuGate::hadamard().apply(&Qubit::<f64>::zero());
+        let measurement = plus.observe_in(&Basis::X);
+        assert!(!measurement.outcome());
+        assert!((measurement.probability() - 1.0).abs() < 1e-12);
+
+        let minus_i = QuGate::s_dagger().apply(&QuGate::hadamard().apply(&Qubit::<f64>::zero()));
+        assert!(minus_i.observe_in(&Basis::Y).outcome());
+        // Rx(-π/2) maps |0⟩ to |+i⟩ and |1⟩ to |−i⟩ up to a global phase.
+        let rx_basis = Basis::from_gate(&QuGate::rx(-std::f64::consts::FRAC_PI_2));
+        let measurement = minus_i.observe_in(&rx_basis);
+        assert!(measurement.outcome());
+        assert!(measurement.state().eq_up_to_global_phase(&minus_i, 1e-12));
+
+        let measurement = plus.observe_in(&Basis::Z);
+        assert!((measurement.probability() - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn expectation() {
+        let qubit = Qubit::<f64>::from_bloch(0.4, 1.1);
+        let (x, y, z) = qubit.bloch_vector();
+
+        assert!((qubit.expectation_x() - x).abs() < 1e-12);
+        assert!((qubit.expectation_y() - y).abs() < 1e-12);
+        assert!((qubit.expectation_z() - z).abs() < 1e-12);
+        assert!((qubit.expectation(&QuGate::hadamard()) - (x + z) / 2.0.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bloch() {
+        use std::f64::consts::{FRAC_PI_2, PI};
+
+        let plus = QuGate::hadamard().apply(&Qubit::<f64>::zero());
+        let (x, y, z) = plus.bloch_vector();
+        assert!((x - 1.0).abs() < 1e-12 && y.abs() < 1e-12 && z.abs() < 1e-12);
+        assert_eq!(Qubit::<f64>::one().bloch_vector(), (0.0, 0.0, -1.0));
+
+        let qubit = Qubit::from_bloch(FRAC_PI_2, -FRAC_PI_2);
+        let (theta, phi) = qubit.bloch_angles();
+        assert!((theta - FRAC_PI_2).abs() < 1e-12 && (phi + FRAC_PI_2).abs() < 1e-12);
+        assert!(qubit.approx_eq(&QuGate::rx(FRAC_PI_2).apply(&Qubit::zero()), 1e-12));
+        assert!(Qubit::<f64>::from_bloch(PI, 0.0).approx_eq(&Qubit::one(), 1e-12));
+    }
+
     #[test]
     fn zero() {
         let qubit = Qubit::<f64>::zero();
@@ -116,4 +489,55 @@ mod tests {
         let qubit = Qubit::<f64>::one();
         assert_eq!(qubit, qubit.measure());
     }
+
+    #[test]
+    fn measure_with() {
+        let qubit = QuGate::hadamard().apply(&Qubit::<f64>::zero());
+        let outcomes = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..16)
+                .map(|_| qubit.measure_with(&mut rng))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(outcomes(7), outcomes(7));
+    }
+
+    #[test]
+    fn observe() {
+        let measurement = Qubit::<f64>::one().observe();
+        assert!(measurement.outcome());
+        assert_eq!(measurement.probability(), 1.0);
+        assert_eq!(measurement.into_state(), Qubit::one());
+
+        let mut qubit = QuGate::hadamard().apply(&Qubit::<f64>::zero());
+        let outcome = qubit.collapse();
+        assert_eq!(qubit, if outcome { Qubit::one() } else { Qubit::zero() });
+    }
+
+    #[test]
+    fn sample() {
+        let counts = Qubit::<f64>::one().sample(100, &mut thread_rng());
+        assert_eq!(counts.get("1"), 100);
+        assert_eq!(counts.len(), 1);
+    }
+
+    #[test]
+    fn zero_norm() {
+        let qubit = Qubit::<f64>::new(0.0 * i, 0.0 * i);
+        assert_eq!(
+            qubit.try_observe_with(&mut thread_rng()),
+            Err(QubError::ZeroNorm)
+        );
+        assert_eq!(
+            qubit.try_sample(10, &mut thread_rng()),
+            Err(QubError::ZeroNorm)
+        );
+    }
+
+    #[test]
+    fn tensor() {
+        let register = Qubit::<f64>::zero().tensor(&Qubit::one());
+        assert_eq!(register, QuRegister::basis(2, 0b01));
+    }
 }
@@ -0,0 +1,621 @@
+use num::Float;
+use rand::prelude::*;
+
+use crate::{
+    error::QubError,
+    qugate::QuGate,
+    quregister::{qubit_masks, QuRegister},
+};
+
+/// An operation recorded in a quantum circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation<T: Float> {
+    /// Apply a gate to the target qubits, only when all control qubits are |1⟩.
+    Gate {
+        /// The name of the gate, e.g. `h` or `rx`.
+        name: String,
+        /// The parameters the gate was built from, e.g. rotation angles.
+        params: Vec<T>,
+        /// The gate acting on the targets, without its controls.
+        gate: QuGate<T>,
+        /// The control qubits of the gate.
+        controls: Vec<usize>,
+        /// The target qubits of the gate.
+        targets: Vec<usize>,
+    },
+    /// Measure a qubit in the computational basis into a classical bit.
+    Measure {
+        /// The measured qubit.
+        qubit: usize,
+        /// The classical bit receiving the outcome.
+        clbit: usize,
+    },
+    /// A barrier across the given qubits, which has no effect on the state.
+    Barrier(Vec<usize>),
+    /// Apply an operation only when the classical bits, read as an integer
+    /// with the first bit as the least significant, equal the given value.
+    Conditional {
+        /// The classical bits forming the integer to compare.
+        clbits: Vec<usize>,
+        /// The value the classical bits must hold.
+        value: usize,
+        /// The operation to apply when the condition holds.
+        operation: Box<Operation<T>>,
+    },
+}
+
+impl<T: Float> Operation<T> {
+    /// Check whether the classical bits hold the value of a condition.
+    fn condition_holds(clbits: &[usize], value: usize, values: &[bool]) -> bool {
+        clbits.iter().enumerate().all(|(bit, &clbit)| {
+            let expected = bit < usize::BITS as usize && (value >> bit) & 1 == 1;
+            values[clbit] == expected
+        })
+    }
+}
+
+/// A quantum circuit is an ordered list of operations over n qubits
+/// and m classical bits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuCircuit<T: Float> {
+    n_qubits: usize,
+    n_clbits: usize,
+    operations: Vec<Operation<T>>,
+}
+
+/// The outcome of running a quantum circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution<T: Float> {
+    state: QuRegister<T>,
+    clbits: Vec<bool>,
+}
+
+impl<T: Float> Execution<T> {
+    /// Get the final state of the register.
+    pub fn state(&self) -> &QuRegister<T> {
+        &self.state
+    }
+
+    /// Get the final values of the classical bits.
+    pub fn clbits(&self) -> &[bool] {
+        &self.clbits
+    }
+}
+
+impl<T: Float + 'static> QuCircuit<T> {
+    /// Create a new empty circuit over n qubits,
+    /// with one classical bit per qubit.
+    pub fn new(n_qubits: usize) -> Self {
+        Self::with_clbits(n_qubits, n_qubits)
+    }
+
+    /// Create a new empty circuit over n qubits and m classical bits.
+    pub fn with_clbits(n_qubits: usize, n_clbits: usize) -> Self {
+        Self {
+            n_qubits,
+            n_clbits,
+            operations: Vec::new(),
+        }
+    }
+
+    /// Get the number of qubits of the circuit.
+    pub fn n_qubits(&self) -> usize {
+        self.n_qubits
+    }
+
+    /// Get the number of classical bits of the circuit.
+    pub fn n_clbits(&self) -> usize {
+        self.n_clbits
+    }
+
+    /// Get the operations of the circuit, in order.
+    pub fn operations(&self) -> &[Operation<T>] {
+        &self.operations
+    }
+
+    /// Append an operation to the circuit.
+    ///
+    /// # Panics
+    /// Panics if the operation refers to qubits or classical bits out of range,
+    /// if a condition value does not fit in its classical bits,
+    /// if a gate repeats a qubit, has a matrix that is not square with a power of two size,
+    /// does not match the number of its target qubits, or has parameters or matrix entries
+    /// that are not finite.
+    pub fn push(&mut self, operation: Operation<T>) {
+        self.try_push(operation)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Append an operation to the circuit,
+    /// failing if the operation refers to qubits or classical bits out of range,
+    /// if a condition value does not fit in its classical bits,
+    /// if a gate repeats a qubit, has a matrix that is not square with a power of two size,
+    /// does not match the number of its target qubits, or has parameters or matrix entries
+    /// that are not finite.
+    pub fn try_push(&mut self, operation: Operation<T>) -> Result<(), QubError> {
+        self.check(&operation)?;
+        self.operations.push(operation);
+        Ok(())
+    }
+
+    fn check(&self, operation: &Operation<T>) -> Result<(), QubError> {
+        match operation {
+            Operation::Gate {
+                params,
+                gate,
+                controls,
+                targets,
+                ..
+            } => {
+                gate.check_size(targets.len())?;
+                if !params.iter().all(|param| param.is_finite())
+                    || !gate
+                        .matrix()
+                        .iter()
+                        .all(|entry| entry.re.is_finite() && entry.im.is_finite())
+                {
+                    return Err(QubError::NonFinite);
+                }
+                let qubits: Vec<usize> = controls.iter().chain(targets).copied().collect();
+                qubit_masks(&qubits, self.n_qubits)?;
+            }
+            Operation::Measure { qubit, clbit } => {
+                qubit_masks(&[*qubit], self.n_qubits)?;
+                self.check_clbit(*clbit)?;
+            }
+            Operation::Barrier(qubits) => {
+                for &qubit in qubits {
+                    qubit_masks(&[qubit], self.n_qubits)?;
+                }
+            }
+            Operation::Conditional {
+                clbits,
+                value,
+                operation,
+            } => {
+                for &clbit in clbits {
+                    self.check_clbit(clbit)?;
+                }
+                let high = u32::try_from(clbits.len())
+                    .ok()
+                    .and_then(|shift| value.checked_shr(shift))
+                    .unwrap_or(0);
+                if high != 0 {
+                    return Err(QubError::ConditionOutOfRange {
+                        value: *value,
+                        n_clbits: clbits.len(),
+                    });
+                }
+                self.check(operation)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_clbit(&self, clbit: usize) -> Result<(), QubError> {
+        if clbit >= self.n_clbits {
+            return Err(QubError::ClbitOutOfRange {
+                clbit,
+                n_clbits: self.n_clbits,
+            });
+        }
+        Ok(())
+    }
+
+    /// Append a named gate acting on the given target qubits.
+    pub fn gate(self, name: &str, gate: QuGate<T>, targets: &[usize]) -> Self {
+        self.controlled_gate(name, Vec::new(), gate, &[], targets)
+    }
+
+    /// Append a named gate acting on the given target qubits,
+    /// controlled by the given control qubits.
+    pub fn controlled_gate(
+        mut self,
+        name: &str,
+        params: Vec<T>,
+        gate: QuGate<T>,
+        controls: &[usize],
+        targets: &[usize],
+    ) -> Self {
+        self.push(Operation::Gate {
+            name: name.to_string(),
+            params,
+            gate,
+            controls: controls.to_vec(),
+            targets: targets.to_vec(),
+        });
+        self
+    }
+
+    /// Append a Pauli-X gate.
+    pub fn x(self, qubit: usize) -> Self {
+        self.gate("x", QuGate::pauli_x(), &[qubit])
+    }
+
+    /// Append a Pauli-Y gate.
+    pub fn y(self, qubit: usize) -> Self {
+        self.gate("y", QuGate::pauli_y(), &[qubit])
+    }
+
+    /// Append a Pauli-Z gate.
+    pub fn z(self, qubit: usize) -> Self {
+        self.gate("z", QuGate::pauli_z(), &[qubit])
+    }
+
+    /// Append a Hadamard gate.
+    pub fn h(self, qubit: usize) -> Self {
+        self.gate("h", QuGate::hadamard(), &[qubit])
+    }
+
+    /// Append an S gate.
+    pub fn s(self, qubit: usize) -> Self {
+        self.gate("s", QuGate::s(), &[qubit])
+    }
+
+    /// Append an S† gate.
+    pub fn sdg(self, qubit: usize) -> Self {
+        self.gate("sdg", QuGate::s_dagger(), &[qubit])
+    }
+
+    /// Append a T gate.
+    pub fn t(self, qubit: usize) -> Self {
+        self.gate("t", QuGate::t(), &[qubit])
+    }
+
+    /// Append a T† gate.
+    pub fn tdg(self, qubit: usize) -> Self {
+        self.gate("tdg", QuGate::t_dagger(), &[qubit])
+    }
+
+    /// Append a rotation around the X axis.
+    pub fn rx(self, theta: T, qubit: usize) -> Self {
+        self.controlled_gate("rx", vec![theta], QuGate::rx(theta), &[], &[qubit])
+    }
+
+    /// Append a rotation around the Y axis.
+    pub fn ry(self, theta: T, qubit: usize) -> Self {
+        self.controlled_gate("ry", vec![theta], QuGate::ry(theta), &[], &[qubit])
+    }
+
+    /// Append a rotation around the Z axis.
+    pub fn rz(self, theta: T, qubit: usize) -> Self {
+        self.controlled_gate("rz", vec![theta], QuGate::rz(theta), &[], &[qubit])
+    }
+
+    /// Append a phase gate.
+    pub fn p(self, lambda: T, qubit: usize) -> Self {
+        self.controlled_gate("p", vec![lambda], QuGate::phase(lambda), &[], &[qubit])
+    }
+
+    /// Append a general U3(θ, φ, λ) gate.
+    pub fn u3(self, theta: T, phi: T, lambda: T, qubit: usize) -> Self {
+        self.controlled_gate(
+            "u3",
+            vec![theta, phi, lambda],
+            QuGate::u3(theta, phi, lambda),
+            &[],
+            &[qubit],
+        )
+    }
+
+    /// Append a controlled-NOT gate.
+    pub fn cx(self, control: usize, target: usize) -> Self {
+        self.controlled_gate("x", Vec::new(), QuGate::pauli_x(), &[control], &[target])
+    }
+
+    /// Append a controlled-Z gate.
+    pub fn cz(self, control: usize, target: usize) -> Self {
+        self.controlled_gate("z", Vec::new(), QuGate::pauli_z(), &[control], &[target])
+    }
+
+    /// Append a SWAP gate.
+    pub fn swap(self, a: usize, b: usize) -> Self {
+        self.gate("swap", QuGate::swap(), &[a, b])
+    }
+
+    /// Append a Toffoli gate.
+    pub fn ccx(self, control_a: usize, control_b: usize, target: usize) -> Self {
+        self.controlled_gate(
+            "x",
+            Vec::new(),
+            QuGate::pauli_x(),
+            &[control_a, control_b],
+            &[target],
+        )
+    }
+
+    /// Append a Fredkin gate.
+    pub fn cswap(self, control: usize, a: usize, b: usize) -> Self {
+        self.controlled_gate("swap", Vec::new(), QuGate::swap(), &[control], &[a, b])
+    }
+
+    /// Append a measurement of the qubit into the classical bit of the same index.
+    pub fn measure(self, qubit: usize) -> Self {
+        self.measure_into(qubit, qubit)
+    }
+
+    /// Append a measurement of the qubit into the given classical bit.
+    pub fn measure_into(mut self, qubit: usize, clbit: usize) -> Self {
+        self.push(Operation::Measure { qubit, clbit });
+        self
+    }
+
+    /// Append a measurement of every qubit into the classical bit of the same index.
+    pub fn measure_all(self) -> Self {
+        (0..self.n_qubits).fold(self, |circuit, qubit| circuit.measure(qubit))
+    }
+
+    /// Append an operation applied only when the classical bits,
+    /// read as an integer with the first bit as the least significant,
+    /// equal the given value.
+    pub fn conditional(mut self, clbits: &[usize], value: usize, operation: Operation<T>) -> Self {
+        self.push(Operation::Conditional {
+            clbits: clbits.to_vec(),
+            value,
+            operation: Box::new(operation),
+        });
+        self
+    }
+
+    /// Append a barrier across every qubit.
+    pub fn barrier(mut self) -> Self {
+        self.push(Operation::Barrier((0..self.n_qubits).collect()));
+        self
+    }
+
+    /// Run the circuit starting from the |0...0⟩ state.
+    pub fn run(&self) -> Execution<T> {
+        self.run_on(&QuRegister::zero(self.n_qubits))
+    }
+
+    /// Run the circuit starting from the |0...0⟩ state,
+    /// drawing measurement randomness from the given random number generator.
+    pub fn run_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Execution<T> {
+        self.run_on_with(&QuRegister::zero(self.n_qubits), rng)
+    }
+
+    /// Run the circuit starting from the |0...0⟩ state,
+    /// with measurement outcomes fully determined by the given seed.
+    pub fn run_seeded(&self, seed: u64) -> Execution<T> {
+        self.run_with(&mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Run the circuit starting from the given state.
+    ///
+    /// # Panics
+    /// Panics if the state does not have as many qubits as the circuit.
+    pub fn run_on(&self, state: &QuRegister<T>) -> Execution<T> {
+        self.run_on_with(state, &mut thread_rng())
+    }
+
+    /// Run the circuit starting from the given state,
+    /// drawing measurement randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if the state does not have as many qubits as the circuit,
+    /// or if it has zero norm.
+    pub fn run_on_with<R: Rng + ?Sized>(&self, state: &QuRegister<T>, rng: &mut R) -> Execution<T> {
+        self.try_run_on_with(state, rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Run the circuit starting from the given state,
+    /// drawing measurement randomness from the given random number generator,
+    /// failing if the state does not have as many qubits as the circuit,
+    /// or if it has zero norm.
+    pub fn try_run_on_with<R: Rng + ?Sized>(
+        &self,
+        state: &QuRegister<T>,
+        rng: &mut R,
+    ) -> Result<Execution<T>, QubError> {
+        if state.n_qubits() != self.n_qubits {
+            return Err(QubError::DimensionMismatch {
+                expected: self.n_qubits,
+                found: state.n_qubits(),
+            });
+        }
+        let mut state = state.clone();
+        let mut clbits = vec![false; self.n_clbits];
+
+        for operation in &self.operations {
+            state = Self::try_apply(operation, state, &mut clbits, rng)?;
+        }
+
+        Ok(Execution { state, clbits })
+    }
+
+    fn try_apply<R: Rng + ?Sized>(
+        operation: &Operation<T>,
+        state: QuRegister<T>,
+        clbits: &mut [bool],
+        rng: &mut R,
+    ) -> Result<QuRegister<T>, QubError> {
+        match operation {
+            Operation::Gate {
+                gate,
+                controls,
+                targets,
+                ..
+            } => {
+                if controls.is_empty() && targets.len() == 1 {
+                    gate.try_apply_to(&state, targets[0])
+                } else {
+                    let qubits: Vec<usize> = controls.iter().chain(targets).copied().collect();
+                    gate.controlled(controls.len())
+                        .try_apply_to_qubits(&state, &qubits)
+                }
+            }
+            Operation::Measure { qubit, clbit } => {
+                let measurement = state.try_measure_qubits_with(&[*qubit], rng)?;
+                clbits[*clbit] = measurement.outcomes()[0];
+                Ok(measurement.into_state())
+            }
+            Operation::Barrier(_) => Ok(state),
+            Operation::Conditional {
+                clbits: condition,
+                value,
+                operation,
+            } => {
+                if Operation::<T>::condition_holds(condition, *value, clbits) {
+                    Self::try_apply(operation, state, clbits, rng)
+                } else {
+                    Ok(state)
+                }
+            }
+        }
+    }
+}
+
+/// Build the gate a circuit records under the given name and parameters,
+/// without its controls, if the name is one of the built-in gates.
+pub(crate) fn named_gate<T: Float + 'static>(name: &str, params: &[T]) -> Option<QuGate<T>> {
+    let gate = match (name, params) {
+        ("id", []) => QuGate::identity(1),
+        ("x", []) => QuGate::pauli_x(),
+        ("y", []) => QuGate::pauli_y(),
+        ("z", []) => QuGate::pauli_z(),
+        ("h", []) => QuGate::hadamard(),
+        ("s", []) => QuGate::s(),
+        ("sdg", []) => QuGate::s_dagger(),
+        ("t", []) => QuGate::t(),
+        ("tdg", []) => QuGate::t_dagger(),
+        ("sx", []) => QuGate::sx(),
+        ("sxdg", []) => QuGate::sx_dagger(),
+        ("rx", &[theta]) => QuGate::rx(theta),
+        ("ry", &[theta]) => QuGate::ry(theta),
+        ("rz", &[theta]) => QuGate::rz(theta),
+        ("p", &[lambda]) => QuGate::phase(lambda),
+        ("u3", &[theta, phi, lambda]) => QuGate::u3(theta, phi, lambda),
+        ("swap", []) => QuGate::swap(),
+        ("rxx", &[theta]) => QuGate::rxx(theta),
+        ("rzz", &[theta]) => QuGate::rzz(theta),
+        _ => return None,
+    };
+    Some(gate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bell_pair() {
+        let circuit = QuCircuit::<f64>::new(2).h(0).cx(0, 1).measure_all();
+        assert_eq!(circuit.operations().len(), 4);
+
+        let execution = circuit.run();
+        let (a, b) = (execution.clbits()[0], execution.clbits()[1]);
+        assert_eq!(a, b);
+        assert_eq!(
+            execution.state(),
+            &QuRegister::basis(2, if a { 0b11 } else { 0b00 })
+        );
+    }
+
+    #[test]
+    fn toffoli() {
+        let circuit = QuCircuit::<f64>::new(3).x(0).x(2).ccx(0, 2, 1).barrier();
+
+        assert_eq!(circuit.run().state(), &QuRegister::basis(3, 0b111));
+        assert_eq!(circuit.run().clbits(), &[false, false, false]);
+    }
+
+    #[test]
+    fn conditional() {
+        let flip = |target| Operation::Gate {
+            name: "x".to_string(),
+            params: Vec::new(),
+            gate: QuGate::pauli_x(),
+            controls: Vec::new(),
+            targets: vec![target],
+        };
+        let circuit = QuCircuit::<f64>::new(3)
+            .x(0)
+            .measure_all()
+            .conditional(&[0, 1], 0b01, flip(1))
+            .conditional(&[0, 1], 0b10, flip(2));
+
+        assert_eq!(circuit.run().state(), &QuRegister::basis(3, 0b110));
+        assert_eq!(circuit.run().clbits(), &[true, false, false]);
+    }
+
+    #[test]
+    fn seeded() {
+        let circuit = QuCircuit::<f64>::new(4).h(0).h(1).h(2).h(3).measure_all();
+
+        for seed in 0..8 {
+            assert_eq!(circuit.run_seeded(seed), circuit.run_seeded(seed));
+        }
+    }
+
+    #[test]
+    fn try_push() {
+        let mut circuit = QuCircuit::<f64>::with_clbits(2, 1);
+
+        assert_eq!(
+            circuit.try_push(Operation::Measure { qubit: 0, clbit: 1 }),
+            Err(QubError::ClbitOutOfRange {
+                clbit: 1,
+                n_clbits: 1
+            })
+        );
+        assert_eq!(
+            circuit.try_push(Operation::Gate {
+                name: "x".to_string(),
+                params: Vec::new(),
+                gate: QuGate::pauli_x(),
+                controls: vec![1],
+                targets: vec![1],
+            }),
+            Err(QubError::DuplicateQubit { qubit: 1 })
+        );
+        assert_eq!(
+            circuit.try_push(Operation::Conditional {
+                clbits: vec![0],
+                value: 2,
+                operation: Box::new(Operation::Barrier(Vec::new())),
+            }),
+            Err(QubError::ConditionOutOfRange {
+                value: 2,
+                n_clbits: 1
+            })
+        );
+        assert_eq!(
+            circuit.try_push(Operation::Gate {
+                name: "unitary".to_string(),
+                params: Vec::new(),
+                gate: QuGate::new(ndarray::Array2::eye(3)),
+                controls: Vec::new(),
+                targets: Vec::new(),
+            }),
+            Err(QubError::InvalidShape {
+                rows: 3,
+                columns: 3
+            })
+        );
+        assert_eq!(
+            circuit.try_push(Operation::Gate {
+                name: "rx".to_string(),
+                params: vec![f64::NAN],
+                gate: QuGate::rx(f64::NAN),
+                controls: Vec::new(),
+                targets: vec![0],
+            }),
+            Err(QubError::NonFinite)
+        );
+        assert!(circuit.operations().is_empty());
+        assert_eq!(
+            circuit.try_run_on_with(&QuRegister::zero(3), &mut thread_rng()),
+            Err(QubError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "qubit out of range")]
+    fn out_of_range() {
+        QuCircuit::<f64>::new(2).cx(0, 2);
+    }
+}
@@ -1,10 +1,15 @@
-use ndarray::prelude::*;
+use ndarray::{linalg::kron, prelude::*};
 use num::{complex::Complex, Float};
 
-use crate::qubit::Qubit;
+use crate::{
+    compare,
+    error::QubError,
+    qubit::Qubit,
+    quregister::{qubit_masks, QuRegister},
+};
 
 /// A quantum gate is a unitary operator that acts on a qubit.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct QuGate<T: Float> {
     matrix: Array2<Complex<T>>,
 }
@@ -15,11 +20,217 @@ impl<T: Float + 'static> QuGate<T> {
         Self { matrix }
     }
 
+    /// Create a new quantum gate with the given matrix, checking that it is
+    /// square with a power of two size and unitary within the given tolerance.
+    pub fn try_new(matrix: Array2<Complex<T>>, tolerance: T) -> Result<Self, QubError> {
+        let (rows, columns) = matrix.dim();
+        if rows != columns || !rows.is_power_of_two() {
+            return Err(QubError::InvalidShape { rows, columns });
+        }
+
+        let gate = Self { matrix };
+        if !gate.is_unitary(tolerance) {
+            return Err(QubError::NotUnitary);
+        }
+
+        Ok(gate)
+    }
+
+    /// Check whether the gate is unitary, i.e. U†U = I, within the given tolerance.
+    pub fn is_unitary(&self, tolerance: T) -> bool {
+        if !self.matrix.is_square() {
+            return false;
+        }
+
+        let product = self.dagger().matrix.dot(&self.matrix);
+        let identity = Array2::<Complex<T>>::eye(self.matrix.nrows());
+        (product - identity).iter().all(|x| x.norm() <= tolerance)
+    }
+
+    /// Check whether this gate equals another within the given tolerance
+    /// on each matrix element.
+    pub fn approx_eq(&self, other: &Self, tolerance: T) -> bool {
+        compare::approx_eq(&self.matrix, &other.matrix, tolerance)
+    }
+
+    /// Check whether this gate equals another within the given tolerance
+    /// up to a global phase, which has no physical effect,
+    /// e.g. Rz(θ) and P(θ) are equal up to global phase.
+    pub fn eq_up_to_global_phase(&self, other: &Self, tolerance: T) -> bool {
+        compare::eq_up_to_global_phase(&self.matrix, &other.matrix, tolerance)
+    }
+
     /// Apply the quantum gate to the given qubit.
+    ///
+    /// # Panics
+    /// Panics if the gate is not a single-qubit gate.
     pub fn apply(&self, qubit: &Qubit<T>) -> Qubit<T> {
-        Qubit {
+        self.try_apply(qubit)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Apply the quantum gate to the given qubit,
+    /// failing if the gate is not a single-qubit gate.
+    pub fn try_apply(&self, qubit: &Qubit<T>) -> Result<Qubit<T>, QubError> {
+        self.check_size(1)?;
+        Ok(Qubit {
             state: self.matrix.dot(&qubit.state),
+        })
+    }
+
+    /// Apply the quantum gate to the whole state of the given register.
+    ///
+    /// # Panics
+    /// Panics if the gate does not act on as many qubits as the register holds.
+    pub fn apply_register(&self, register: &QuRegister<T>) -> QuRegister<T> {
+        self.try_apply_register(register)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Apply the quantum gate to the whole state of the given register,
+    /// failing if the gate does not act on as many qubits as the register holds.
+    pub fn try_apply_register(&self, register: &QuRegister<T>) -> Result<QuRegister<T>, QubError> {
+        self.check_size(register.n_qubits())?;
+        Ok(QuRegister {
+            state: self.matrix.dot(&register.state),
+        })
+    }
+
+    /// Apply a single-qubit gate to the target qubit of the given register,
+    /// leaving the other qubits untouched.
+    /// This avoids building the full 2^n x 2^n operator.
+    ///
+    /// # Panics
+    /// Panics if the gate is not a single-qubit gate or the target is out of range.
+    pub fn apply_to(&self, register: &QuRegister<T>, target: usize) -> QuRegister<T> {
+        self.try_apply_to(register, target)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Apply a single-qubit gate to the target qubit of the given register,
+    /// failing if the gate is not a single-qubit gate or the target is out of range.
+    pub fn try_apply_to(
+        &self,
+        register: &QuRegister<T>,
+        target: usize,
+    ) -> Result<QuRegister<T>, QubError> {
+        self.check_size(1)?;
+        let stride = qubit_masks(&[target], register.n_qubits())?[0];
+        let mut state = register.state.clone();
+        let m = &self.matrix;
+
+        for index in (0..state.len()).filter(|index| index & stride == 0) {
+            let a0 = state[index];
+            let a1 = state[index | stride];
+            state[index] = m[[0, 0]] * a0 + m[[0, 1]] * a1;
+            state[index | stride] = m[[1, 0]] * a0 + m[[1, 1]] * a1;
         }
+
+        Ok(QuRegister { state })
+    }
+
+    /// Apply the quantum gate to the given qubits of the register, in order.
+    /// The first qubit in the list is the most significant qubit of the gate,
+    /// so the controls of a controlled gate come first.
+    /// This avoids building the full 2^n x 2^n operator.
+    ///
+    /// # Panics
+    /// Panics if the number of qubits does not match the gate,
+    /// or if a qubit is out of range or repeated.
+    pub fn apply_to_qubits(&self, register: &QuRegister<T>, qubits: &[usize]) -> QuRegister<T> {
+        self.try_apply_to_qubits(register, qubits)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Apply the quantum gate to the given qubits of the register, in order,
+    /// failing if the number of qubits does not match the gate,
+    /// or if a qubit is out of range or repeated.
+    pub fn try_apply_to_qubits(
+        &self,
+        register: &QuRegister<T>,
+        qubits: &[usize],
+    ) -> Result<QuRegister<T>, QubError> {
+        self.check_size(qubits.len())?;
+        let masks = qubit_masks(qubits, register.n_qubits())?;
+        let full_mask = masks.iter().fold(0, |acc, mask| acc | mask);
+
+        // Basis state offsets for each column of the gate matrix.
+        let dim = self.matrix.nrows();
+        let offsets: Vec<usize> = (0..dim)
+            .map(|column| {
+                masks
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| column & (1 << (masks.len() - 1 - j)) != 0)
+                    .fold(0, |acc, (_, mask)| acc | mask)
+            })
+            .collect();
+
+        let mut state = register.state.clone();
+        let mut amplitudes = Array1::from_elem(dim, Complex::new(T::zero(), T::zero()));
+
+        for base in (0..state.len()).filter(|index| index & full_mask == 0) {
+            for (amplitude, offset) in amplitudes.iter_mut().zip(&offsets) {
+                *amplitude = state[base | offset];
+            }
+            for (amplitude, offset) in self.matrix.dot(&amplitudes).iter().zip(&offsets) {
+                state[base | offset] = *amplitude;
+            }
+        }
+
+        Ok(QuRegister { state })
+    }
+
+    /// Check that the gate matrix is square with a power of two size
+    /// and acts on the given number of qubits.
+    pub(crate) fn check_size(&self, n_qubits: usize) -> Result<(), QubError> {
+        let (rows, columns) = self.matrix.dim();
+        if rows != columns || !rows.is_power_of_two() {
+            return Err(QubError::InvalidShape { rows, columns });
+        }
+        if self.n_qubits() == n_qubits {
+            Ok(())
+        } else {
+            Err(QubError::DimensionMismatch {
+                expected: self.n_qubits(),
+                found: n_qubits,
+            })
+        }
+    }
+
+    /// Get the matrix of the quantum gate.
+    pub fn matrix(&self) -> &Array2<Complex<T>> {
+        &self.matrix
+    }
+
+    /// Get the number of qubits the quantum gate acts on.
+    pub fn n_qubits(&self) -> usize {
+        self.matrix.nrows().trailing_zeros() as usize
+    }
+
+    /// Get the inverse of this gate, its conjugate transpose U†.
+    pub fn dagger(&self) -> Self {
+        Self::new(self.matrix.t().mapv(|x| x.conj()))
+    }
+
+    /// Combine this gate with another into the joint operator self ⊗ other.
+    /// The qubits acted on by `self` come first.
+    pub fn tensor(&self, other: &Self) -> Self {
+        Self::new(kron(&self.matrix, &other.matrix))
+    }
+
+    /// Create the controlled form of this gate with the given number of control qubits.
+    /// The controls are the leading qubits of the new gate,
+    /// and this gate is applied to the remaining qubits only when all controls are |1⟩.
+    pub fn controlled(&self, n_controls: usize) -> Self {
+        let dim = self.matrix.nrows() << n_controls;
+        let offset = dim - self.matrix.nrows();
+        let mut matrix = Array2::eye(dim);
+        matrix
+            .slice_mut(s![offset.., offset..])
+            .assign(&self.matrix);
+
+        Self::new(matrix)
     }
 }
 
@@ -43,7 +254,7 @@ impl<T: Float + 'static> QuGate<T> {
         Self::new(array![
             [
                 Complex::new(T::zero(), T::zero()),
-                Complex::new(T::zero(), T::one())
+                Complex::new(T::zero(), -T::one())
             ],
             [
                 Complex::new(T::zero(), T::one()),
@@ -68,7 +279,7 @@ impl<T: Float + 'static> QuGate<T> {
 
     /// Create a Hadamard gate.
     pub fn hadamard() -> Self {
-        let norm_factor = T::one() / (T::from(2.0).unwrap().sqrt());
+        let norm_factor = T::one() / (T::one() + T::one()).sqrt();
         Self::new(array![
             [
                 Complex::new(norm_factor, T::zero()),
@@ -80,6 +291,190 @@ impl<T: Float + 'static> QuGate<T> {
             ],
         ])
     }
+
+    /// Create a rotation gate around the X axis of the Bloch sphere by the given angle.
+    pub fn rx(theta: T) -> Self {
+        let half = theta / (T::one() + T::one());
+        let (sin, cos) = half.sin_cos();
+        Self::new(array![
+            [Complex::new(cos, T::zero()), Complex::new(T::zero(), -sin)],
+            [Complex::new(T::zero(), -sin), Complex::new(cos, T::zero())],
+        ])
+    }
+
+    /// Create a rotation gate around the Y axis of the Bloch sphere by the given angle.
+    pub fn ry(theta: T) -> Self {
+        let half = theta / (T::one() + T::one());
+        let (sin, cos) = half.sin_cos();
+        Self::new(array![
+            [Complex::new(cos, T::zero()), Complex::new(-sin, T::zero())],
+            [Complex::new(sin, T::zero()), Complex::new(cos, T::zero())],
+        ])
+    }
+
+    /// Create a rotation gate around the Z axis of the Bloch sphere by the given angle.
+    pub fn rz(theta: T) -> Self {
+        let half = theta / (T::one() + T::one());
+        Self::new(array![
+            [Complex::cis(-half), Complex::new(T::zero(), T::zero())],
+            [Complex::new(T::zero(), T::zero()), Complex::cis(half)],
+        ])
+    }
+
+    /// Create a phase gate, adding the given phase to the |1⟩ state.
+    pub fn phase(lambda: T) -> Self {
+        Self::new(array![
+            [
+                Complex::new(T::one(), T::zero()),
+                Complex::new(T::zero(), T::zero())
+            ],
+            [Complex::new(T::zero(), T::zero()), Complex::cis(lambda)],
+        ])
+    }
+
+    /// Create an S gate, the square root of the Pauli-Z gate.
+    pub fn s() -> Self {
+        Self::phase_shift(Complex::new(T::zero(), T::one()))
+    }
+
+    /// Create an S† gate, the inverse of the S gate.
+    pub fn s_dagger() -> Self {
+        Self::phase_shift(Complex::new(T::zero(), -T::one()))
+    }
+
+    /// Create a T gate, the square root of the S gate.
+    pub fn t() -> Self {
+        let norm_factor = T::one() / (T::one() + T::one()).sqrt();
+        Self::phase_shift(Complex::new(norm_factor, norm_factor))
+    }
+
+    /// Create a T† gate, the inverse of the T gate.
+    pub fn t_dagger() -> Self {
+        let norm_factor = T::one() / (T::one() + T::one()).sqrt();
+        Self::phase_shift(Complex::new(norm_factor, -norm_factor))
+    }
+
+    /// Create a gate multiplying the |1⟩ state by the given unit complex number.
+    fn phase_shift(factor: Complex<T>) -> Self {
+        Self::new(array![
+            [
+                Complex::new(T::one(), T::zero()),
+                Complex::new(T::zero(), T::zero())
+            ],
+            [Complex::new(T::zero(), T::zero()), factor],
+        ])
+    }
+
+    /// Create a general single-qubit gate U3(θ, φ, λ) = Rz(φ) Ry(θ) Rz(λ),
+    /// up to a global phase.
+    pub fn u3(theta: T, phi: T, lambda: T) -> Self {
+        let half = theta / (T::one() + T::one());
+        let (sin, cos) = half.sin_cos();
+        Self::new(array![
+            [Complex::new(cos, T::zero()), -Complex::cis(lambda) * sin],
+            [Complex::cis(phi) * sin, Complex::cis(phi + lambda) * cos],
+        ])
+    }
+
+    /// Create a √X gate, the square root of the Pauli-X gate.
+    pub fn sx() -> Self {
+        let half = T::one() / (T::one() + T::one());
+        Self::new(array![
+            [Complex::new(half, half), Complex::new(half, -half)],
+            [Complex::new(half, -half), Complex::new(half, half)],
+        ])
+    }
+
+    /// Create a √X† gate, the inverse of the √X gate.
+    pub fn sx_dagger() -> Self {
+        Self::sx().dagger()
+    }
+
+    /// Create an Ising XX coupling gate exp(-iθ/2 X⊗X) on two qubits.
+    pub fn rxx(theta: T) -> Self {
+        let half = theta / (T::one() + T::one());
+        let (sin, cos) = half.sin_cos();
+        Self::new(Array2::from_shape_fn((4, 4), |(row, column)| {
+            if row == column {
+                Complex::new(cos, T::zero())
+            } else if row + column == 3 {
+                Complex::new(T::zero(), -sin)
+            } else {
+                Complex::new(T::zero(), T::zero())
+            }
+        }))
+    }
+
+    /// Create an Ising ZZ coupling gate exp(-iθ/2 Z⊗Z) on two qubits.
+    pub fn rzz(theta: T) -> Self {
+        let half = theta / (T::one() + T::one());
+        Self::new(Array2::from_shape_fn((4, 4), |(row, column)| {
+            match (row == column, row) {
+                (false, _) => Complex::new(T::zero(), T::zero()),
+                (true, 0 | 3) => Complex::cis(-half),
+                (true, _) => Complex::cis(half),
+            }
+        }))
+    }
+
+    /// Create an identity gate acting on the given number of qubits.
+    pub fn identity(n_qubits: usize) -> Self {
+        Self::new(Array2::eye(1 << n_qubits))
+    }
+
+    /// Create a controlled-NOT gate, with the control as the first qubit.
+    pub fn cnot() -> Self {
+        Self::pauli_x().controlled(1)
+    }
+
+    /// Create a controlled-Z gate.
+    pub fn cz() -> Self {
+        Self::pauli_z().controlled(1)
+    }
+
+    /// Create a SWAP gate, exchanging the states of two qubits.
+    pub fn swap() -> Self {
+        let mut matrix = Array2::eye(4);
+        matrix.swap([1, 1], [1, 2]);
+        matrix.swap([2, 2], [2, 1]);
+
+        Self::new(matrix)
+    }
+
+    /// Create a Toffoli (controlled-controlled-NOT) gate,
+    /// with the controls as the first two qubits.
+    pub fn toffoli() -> Self {
+        Self::pauli_x().controlled(2)
+    }
+
+    /// Create a Fredkin (controlled-SWAP) gate, with the control as the first qubit.
+    pub fn fredkin() -> Self {
+        Self::swap().controlled(1)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl<T: Float + 'static + approx::AbsDiffEq<Epsilon = T>> approx::AbsDiffEq for QuGate<T> {
+    type Epsilon = T;
+
+    fn default_epsilon() -> T {
+        T::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: T) -> bool {
+        self.approx_eq(other, epsilon)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl<T: Float + 'static + approx::RelativeEq<Epsilon = T>> approx::RelativeEq for QuGate<T> {
+    fn default_max_relative() -> T {
+        T::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: T, max_relative: T) -> bool {
+        compare::relative_eq(&self.matrix, &other.matrix, epsilon, max_relative)
+    }
 }
 
 #[cfg(test)]
@@ -89,6 +484,10 @@ mod tests {
     #[allow(non_upper_case_globals)]
     const i: Complex<f64> = Complex::I;
 
+    fn assert_close(a: &QuGate<f64>, b: &QuGate<f64>) {
+        assert!(a.approx_eq(b, 1e-12), "{a:?} != {b:?}");
+    }
+
     #[test]
     fn pauli_x() {
         let qubit = Qubit::<f64>::zero();
@@ -106,6 +505,10 @@ mod tests {
             y_gate.apply(&qubit),
             Qubit::new(0.0 + 0.0 * i, 0.0 + 1.0 * i)
         );
+        assert_eq!(
+            y_gate.apply(&Qubit::one()),
+            Qubit::new(0.0 - 1.0 * i, 0.0 + 0.0 * i)
+        );
     }
 
     #[test]
@@ -127,4 +530,226 @@ mod tests {
             Qubit::new(norm_factor + 0.0 * i, norm_factor + 0.0 * i,)
         );
     }
+
+    #[test]
+    fn try_new() {
+        assert_eq!(
+            QuGate::try_new(Array2::<Complex<f64>>::eye(3), 1e-12),
+            Err(QubError::InvalidShape {
+                rows: 3,
+                columns: 3
+            })
+        );
+        assert_eq!(
+            QuGate::try_new(Array2::<Complex<f64>>::zeros((2, 4)), 1e-12),
+            Err(QubError::InvalidShape {
+                rows: 2,
+                columns: 4
+            })
+        );
+        assert_eq!(
+            QuGate::try_new(
+                array![[1.0 + 0.0 * i, 1.0 + 0.0 * i], [0.0 * i, 1.0 + 0.0 * i]],
+                1e-12
+            ),
+            Err(QubError::NotUnitary)
+        );
+        assert_eq!(
+            QuGate::try_new(QuGate::hadamard().matrix().clone(), 1e-12),
+            Ok(QuGate::hadamard())
+        );
+    }
+
+    #[test]
+    fn try_apply() {
+        let register = QuRegister::<f64>::zero(2);
+
+        assert_eq!(
+            QuGate::<f64>::cnot().try_apply(&Qubit::zero()),
+            Err(QubError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            QuGate::hadamard().try_apply_to(&register, 2),
+            Err(QubError::QubitOutOfRange {
+                qubit: 2,
+                n_qubits: 2
+            })
+        );
+        assert_eq!(
+            QuGate::swap().try_apply_to_qubits(&register, &[0, 0]),
+            Err(QubError::DuplicateQubit { qubit: 0 })
+        );
+        assert_eq!(
+            QuGate::new(Array2::<Complex<f64>>::eye(6)).try_apply(&Qubit::zero()),
+            Err(QubError::InvalidShape {
+                rows: 6,
+                columns: 6
+            })
+        );
+        assert_eq!(
+            QuGate::new(Array2::<Complex<f64>>::zeros((2, 4))).try_apply_register(&register),
+            Err(QubError::InvalidShape {
+                rows: 2,
+                columns: 4
+            })
+        );
+    }
+
+    #[test]
+    fn is_unitary() {
+        assert!(QuGate::<f64>::u3(0.1, 0.2, 0.3).is_unitary(1e-12));
+        assert!(QuGate::<f64>::toffoli().is_unitary(0.0));
+        assert!(!QuGate::new(array![[2.0 + 0.0 * i]]).is_unitary(1e-12));
+        assert!(!QuGate::new(Array2::<Complex<f64>>::zeros((2, 4))).is_unitary(1e-12));
+    }
+
+    #[test]
+    fn tensor() {
+        let register = Qubit::<f64>::zero().tensor(&Qubit::zero());
+        let xi_gate = QuGate::pauli_x().tensor(&QuGate::new(Array2::eye(2)));
+
+        assert_eq!(xi_gate.n_qubits(), 2);
+        assert_eq!(
+            xi_gate.apply_register(&register),
+            QuRegister::basis(2, 0b10)
+        );
+    }
+
+    #[test]
+    fn apply_to() {
+        let register = QuRegister::<f64>::zero(3);
+        let x_gate = QuGate::pauli_x();
+        let h_gate = QuGate::hadamard();
+
+        assert_eq!(x_gate.apply_to(&register, 1), QuRegister::basis(3, 0b010));
+        assert_eq!(
+            h_gate.apply_to(&register, 2),
+            QuGate::new(Array2::eye(4))
+                .tensor(&h_gate)
+                .apply_register(&register)
+        );
+    }
+
+    #[test]
+    fn cnot() {
+        let cx_gate = QuGate::<f64>::cnot();
+        let bell = cx_gate.apply_to_qubits(
+            &QuGate::hadamard().apply_to(&QuRegister::zero(2), 0),
+            &[0, 1],
+        );
+        let norm_factor = 1.0 / 2.0.sqrt();
+
+        assert_eq!(
+            bell,
+            QuRegister::new(array![
+                norm_factor + 0.0 * i,
+                0.0 + 0.0 * i,
+                0.0 + 0.0 * i,
+                norm_factor + 0.0 * i
+            ])
+        );
+        assert_eq!(
+            cx_gate.apply_to_qubits(&QuRegister::basis(3, 0b001), &[2, 0]),
+            QuRegister::basis(3, 0b101)
+        );
+    }
+
+    #[test]
+    fn swap() {
+        let register = QuRegister::<f64>::basis(3, 0b100);
+
+        assert_eq!(
+            QuGate::swap().apply_to_qubits(&register, &[0, 2]),
+            QuRegister::basis(3, 0b001)
+        );
+        assert_eq!(
+            QuGate::<f64>::fredkin().apply_to_qubits(&QuRegister::basis(3, 0b110), &[0, 1, 2]),
+            QuRegister::basis(3, 0b101)
+        );
+    }
+
+    #[test]
+    fn toffoli() {
+        let ccx_gate = QuGate::<f64>::toffoli();
+
+        assert_eq!(ccx_gate, QuGate::cnot().controlled(1));
+        assert_eq!(
+            ccx_gate.apply_to_qubits(&QuRegister::basis(3, 0b101), &[0, 2, 1]),
+            QuRegister::basis(3, 0b111)
+        );
+        assert_eq!(
+            ccx_gate.apply_to_qubits(&QuRegister::basis(3, 0b100), &[0, 2, 1]),
+            QuRegister::basis(3, 0b100)
+        );
+    }
+
+    #[test]
+    fn rotations() {
+        use std::f64::consts::PI;
+
+        assert_close(
+            &QuGate::rx(PI),
+            &QuGate::new(QuGate::pauli_x().matrix() * -i),
+        );
+        assert_close(
+            &QuGate::rz(PI / 2.0),
+            &QuGate::new(QuGate::s().matrix() * Complex::cis(-PI / 4.0)),
+        );
+        assert_close(&QuGate::ry(PI / 2.0), &QuGate::u3(PI / 2.0, 0.0, 0.0));
+        assert_close(&QuGate::u3(PI / 2.0, 0.0, PI), &QuGate::hadamard());
+    }
+
+    #[test]
+    fn phases() {
+        let s_gate = QuGate::<f64>::s();
+        let t_gate = QuGate::<f64>::t();
+
+        assert_close(
+            &QuGate::new(s_gate.matrix().dot(s_gate.matrix())),
+            &QuGate::pauli_z(),
+        );
+        assert_close(&QuGate::new(t_gate.matrix().dot(t_gate.matrix())), &s_gate);
+        assert_close(&s_gate.dagger(), &QuGate::s_dagger());
+        assert_close(&t_gate.dagger(), &QuGate::t_dagger());
+    }
+
+    #[test]
+    fn square_roots_and_couplings() {
+        use std::f64::consts::PI;
+
+        let sx_gate = QuGate::<f64>::sx();
+        assert_close(
+            &QuGate::new(sx_gate.matrix().dot(sx_gate.matrix())),
+            &QuGate::pauli_x(),
+        );
+        assert_close(&sx_gate.dagger(), &QuGate::sx_dagger());
+        assert!(sx_gate.eq_up_to_global_phase(&QuGate::rx(PI / 2.0), 1e-12));
+
+        let theta = 0.7;
+        let h_h = QuGate::hadamard().tensor(&QuGate::hadamard());
+        assert_close(
+            &QuGate::new(
+                h_h.matrix()
+                    .dot(QuGate::rzz(theta).matrix())
+                    .dot(h_h.matrix()),
+            ),
+            &QuGate::rxx(theta),
+        );
+        assert!(QuGate::rzz(theta).is_unitary(1e-12));
+    }
+
+    #[test]
+    fn global_phase() {
+        use std::f64::consts::PI;
+
+        let rz_gate = QuGate::rz(PI / 3.0);
+        let p_gate = QuGate::phase(PI / 3.0);
+
+        assert!(!rz_gate.approx_eq(&p_gate, 1e-12));
+        assert!(rz_gate.eq_up_to_global_phase(&p_gate, 1e-12));
+        assert!(!rz_gate.eq_up_to_global_phase(&QuGate::phase(PI / 4.0), 1e-12));
+    }
 }
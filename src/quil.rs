@@ -0,0 +1,1262 @@
+use std::{collections::HashMap, f64::consts::PI, iter};
+
+use ndarray::prelude::*;
+use num::{complex::Complex, Float};
+
+use crate::{
+    error::QubError,
+    qasm::{format_number, number_length},
+    qucircuit::{named_gate, Operation, QuCircuit},
+    qugate::QuGate,
+};
+
+/// The standard Quil gates, with their number of parameters and qubits,
+/// and the name and number of controls they are recorded with in a circuit.
+const STANDARD_GATES: &[(&str, usize, usize, &str, usize)] = &[
+    ("I", 0, 1, "id", 0),
+    ("X", 0, 1, "x", 0),
+    ("Y", 0, 1, "y", 0),
+    ("Z", 0, 1, "z", 0),
+    ("H", 0, 1, "h", 0),
+    ("S", 0, 1, "s", 0),
+    ("T", 0, 1, "t", 0),
+    ("PHASE", 1, 1, "p", 0),
+    ("RX", 1, 1, "rx", 0),
+    ("RY", 1, 1, "ry", 0),
+    ("RZ", 1, 1, "rz", 0),
+    ("CNOT", 0, 2, "x", 1),
+    ("CZ", 0, 2, "z", 1),
+    ("CPHASE", 1, 2, "p", 1),
+    ("SWAP", 0, 2, "swap", 0),
+    ("CCNOT", 0, 3, "x", 2),
+    ("CSWAP", 0, 3, "swap", 1),
+    ("ISWAP", 0, 2, "iswap", 0),
+    ("PSWAP", 1, 2, "pswap", 0),
+    ("CPHASE00", 1, 2, "cphase00", 0),
+    ("CPHASE01", 1, 2, "cphase01", 0),
+    ("CPHASE10", 1, 2, "cphase10", 0),
+];
+
+/// Get the gate recorded under a circuit name with the given parameters,
+/// including the standard Quil gates without a counterpart in `QuGate`.
+fn builtin_gate<T: Float + 'static>(name: &str, params: &[T]) -> Option<QuGate<T>> {
+    let zero = Complex::new(T::zero(), T::zero());
+    let one = Complex::new(T::one(), T::zero());
+    let swap_with = |phase: Complex<T>| {
+        QuGate::new(array![
+            [one, zero, zero, zero],
+            [zero, zero, phase, zero],
+            [zero, phase, zero, zero],
+            [zero, zero, zero, one],
+        ])
+    };
+    let phase_on = |index: usize, phi: T| {
+        let mut diagonal = Array1::from_elem(4, one);
+        diagonal[index] = Complex::cis(phi);
+        QuGate::new(Array2::from_diag(&diagonal))
+    };
+
+    let gate = match (name, params) {
+        ("iswap", []) => swap_with(Complex::new(T::zero(), T::one())),
+        ("pswap", &[theta]) => swap_with(Complex::cis(theta)),
+        ("cphase00", &[phi]) => phase_on(0b00, phi),
+        ("cphase01", &[phi]) => phase_on(0b01, phi),
+        ("cphase10", &[phi]) => phase_on(0b10, phi),
+        _ => return named_gate(name, params),
+    };
+    Some(gate)
+}
+
+/// The definition of the U3 gate, which Quil lacks.
+const U3_DEFINITION: &str = "DEFGATE U3(%theta, %phi, %lambda):
+    cos(%theta/2), -cis(%lambda)*sin(%theta/2)
+    cis(%phi)*sin(%theta/2), cis(%phi+%lambda)*cos(%theta/2)";
+
+/// The definition of the √X gate, which Quil lacks.
+const SX_DEFINITION: &str = "DEFGATE SX:
+    0.5+0.5i, 0.5-0.5i
+    0.5-0.5i, 0.5+0.5i";
+
+/// The definition of the XX coupling gate, which Quil lacks.
+const RXX_DEFINITION: &str = "DEFGATE RXX(%theta):
+    cos(%theta/2), 0, 0, -i*sin(%theta/2)
+    0, cos(%theta/2), -i*sin(%theta/2), 0
+    0, -i*sin(%theta/2), cos(%theta/2), 0
+    -i*sin(%theta/2), 0, 0, cos(%theta/2)";
+
+/// The definition of the ZZ coupling gate, which Quil lacks.
+const RZZ_DEFINITION: &str = "DEFGATE RZZ(%theta):
+    cis(-%theta/2), 0, 0, 0
+    0, cis(%theta/2), 0, 0
+    0, 0, cis(%theta/2), 0
+    0, 0, 0, cis(-%theta/2)";
+
+/// Names a custom gate definition may not take when exporting.
+const RESERVED_NAMES: &[&str] = &[
+    "U3",
+    "SX",
+    "RXX",
+    "RZZ",
+    "CONTROLLED",
+    "DAGGER",
+    "FORKED",
+    "MEASURE",
+    "DECLARE",
+    "DEFGATE",
+    "LABEL",
+    "JUMP",
+    "HALT",
+    "NOP",
+    "RESET",
+    "PRAGMA",
+];
+
+/// Get the name and parameters of the inverse of a built-in gate,
+/// if it is itself a built-in gate.
+fn dagger_name<T: Float>(name: &str, params: &[T]) -> Option<(&'static str, Vec<T>)> {
+    let negated = || params.iter().map(|&param| -param).collect();
+    match (name, params) {
+        ("s", _) => Some(("sdg", Vec::new())),
+        ("sdg", _) => Some(("s", Vec::new())),
+        ("t", _) => Some(("tdg", Vec::new())),
+        ("tdg", _) => Some(("t", Vec::new())),
+        ("sx", _) => Some(("sxdg", Vec::new())),
+        ("sxdg", _) => Some(("sx", Vec::new())),
+        ("id", _) => Some(("id", Vec::new())),
+        ("x", _) => Some(("x", Vec::new())),
+        ("y", _) => Some(("y", Vec::new())),
+        ("z", _) => Some(("z", Vec::new())),
+        ("h", _) => Some(("h", Vec::new())),
+        ("swap", _) => Some(("swap", Vec::new())),
+        ("rx", _) => Some(("rx", negated())),
+        ("ry", _) => Some(("ry", negated())),
+        ("rz", _) => Some(("rz", negated())),
+        ("p", _) => Some(("p", negated())),
+        ("rxx", _) => Some(("rxx", negated())),
+        ("rzz", _) => Some(("rzz", negated())),
+        ("pswap", _) => Some(("pswap", negated())),
+        ("cphase00", _) => Some(("cphase00", negated())),
+        ("cphase01", _) => Some(("cphase01", negated())),
+        ("cphase10", _) => Some(("cphase10", negated())),
+        ("u3", &[theta, phi, lambda]) => Some(("u3", vec![-theta, -lambda, -phi])),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Param(String),
+    Label(String),
+    Int(usize),
+    Real(f64),
+    Imaginary(f64),
+    Symbol(char),
+}
+
+/// Get the length of the identifier at the start of the text,
+/// which may contain dashes between letters, as in `JUMP-WHEN`.
+fn identifier_length(text: &str) -> usize {
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let dash = c == '-'
+            && chars
+                .peek()
+                .is_some_and(|(_, next)| next.is_ascii_alphabetic());
+        if !(c.is_ascii_alphanumeric() || c == '_' || dash) {
+            return index;
+        }
+    }
+    text.len()
+}
+
+/// Split a line of a program into tokens.
+fn tokenize(text: &str, line: usize) -> Result<Vec<Token>, QubError> {
+    let error = |message: String| QubError::Parse { line, message };
+    let mut tokens = Vec::new();
+    let mut rest = text;
+
+    while let Some(next) = rest.chars().next() {
+        let length = if next.is_whitespace() {
+            next.len_utf8()
+        } else if next.is_ascii_alphabetic() || matches!(next, '_' | '%' | '@') {
+            let start = usize::from(matches!(next, '%' | '@'));
+            let length = start + identifier_length(&rest[start..]);
+            let name = rest[start..length].to_string();
+            if name.is_empty() {
+                return Err(error(format!("expected a name after {next:?}")));
+            }
+            tokens.push(match next {
+                '%' => Token::Param(name),
+                '@' => Token::Label(name),
+                _ => Token::Ident(name),
+            });
+            length
+        } else if next.is_ascii_digit()
+            || (next == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let length = number_length(rest);
+            let text = &rest[..length];
+            let imaginary = rest[length..].starts_with('i')
+                && !rest[length + 1..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+
+            let token = if imaginary {
+                text.parse().map(Token::Imaginary).ok()
+            } else if text.contains(['.', 'e', 'E']) {
+                text.parse().map(Token::Real).ok()
+            } else {
+                text.parse().map(Token::Int).ok()
+            };
+            tokens.push(token.ok_or_else(|| error(format!("invalid number {text}")))?);
+            length + usize::from(imaginary)
+        } else if "()[],:+-*/^".contains(next) {
+            tokens.push(Token::Symbol(next));
+            1
+        } else {
+            return Err(error(format!("unexpected character {next:?}")));
+        };
+
+        rest = &rest[length..];
+    }
+
+    Ok(tokens)
+}
+
+/// A complex parameter expression, evaluated once the parameters of a gate are known.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Imaginary(f64),
+    Pi,
+    Param(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Box<Expr>),
+}
+
+const FUNCTIONS: &[&str] = &["sin", "cos", "sqrt", "exp", "cis"];
+
+impl Expr {
+    fn eval<T: Float>(&self, params: &HashMap<&str, T>) -> Result<Complex<T>, QubError> {
+        let real = |value: f64| T::from(value).ok_or(QubError::NumericConversion);
+        let value = match self {
+            Self::Number(value) => Complex::new(real(*value)?, T::zero()),
+            Self::Imaginary(value) => Complex::new(T::zero(), real(*value)?),
+            Self::Pi => Complex::new(real(PI)?, T::zero()),
+            Self::Param(name) => Complex::new(params[name.as_str()], T::zero()),
+            Self::Neg(expr) => -expr.eval(params)?,
+            Self::Binary(op, left, right) => {
+                let (left, right) = (left.eval(params)?, right.eval(params)?);
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    _ => left.powc(right),
+                }
+            }
+            Self::Call(function, argument) => {
+                let argument = argument.eval(params)?;
+                match function.as_str() {
+                    "sin" => argument.sin(),
+                    "cos" => argument.cos(),
+                    "sqrt" => argument.sqrt(),
+                    "exp" => argument.exp(),
+                    _ => (argument * Complex::i()).exp(),
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// The tokens of a single line of a program.
+struct Line {
+    tokens: Vec<Token>,
+    position: usize,
+    number: usize,
+}
+
+impl Line {
+    fn error(&self, message: impl Into<String>) -> QubError {
+        QubError::Parse {
+            line: self.number,
+            message: message.into(),
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position == self.tokens.len()
+    }
+
+    fn next(&mut self) -> Result<Token, QubError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| self.error("unexpected end of line"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        let found = self.tokens.get(self.position) == Some(&Token::Symbol(symbol));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), QubError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {symbol:?}")))
+        }
+    }
+
+    fn end(&self) -> Result<(), QubError> {
+        match self.tokens.get(self.position) {
+            None => Ok(()),
+            Some(token) => Err(self.error(format!("unexpected {token:?}"))),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, QubError> {
+        match self.next()? {
+            Token::Ident(name) => Ok(name),
+            _ => Err(self.error("expected an identifier")),
+        }
+    }
+
+    fn int(&mut self) -> Result<usize, QubError> {
+        match self.next()? {
+            Token::Int(value) => Ok(value),
+            _ => Err(self.error("expected an integer")),
+        }
+    }
+
+    fn label(&mut self) -> Result<String, QubError> {
+        match self.next()? {
+            Token::Label(name) => Ok(name),
+            _ => Err(self.error("expected a label")),
+        }
+    }
+
+    fn expressions(&mut self, params: &[String]) -> Result<Vec<Expr>, QubError> {
+        let mut exprs = vec![self.expression(params)?];
+        while self.eat(',') {
+            exprs.push(self.expression(params)?);
+        }
+        Ok(exprs)
+    }
+
+    fn expression(&mut self, params: &[String]) -> Result<Expr, QubError> {
+        let mut expr = self.term(params)?;
+        while let Some(op) = ['+', '-'].into_iter().find(|&op| self.eat(op)) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term(params)?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self, params: &[String]) -> Result<Expr, QubError> {
+        let mut expr = self.unary(params)?;
+        while let Some(op) = ['*', '/'].into_iter().find(|&op| self.eat(op)) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary(params)?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self, params: &[String]) -> Result<Expr, QubError> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary(params)?)));
+        }
+
+        let base = self.primary(params)?;
+        if self.eat('^') {
+            Ok(Expr::Binary(
+                '^',
+                Box::new(base),
+                Box::new(self.unary(params)?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self, params: &[String]) -> Result<Expr, QubError> {
+        match self.next()? {
+            Token::Int(value) => Ok(Expr::Number(value as f64)),
+            Token::Real(value) => Ok(Expr::Number(value)),
+            Token::Imaginary(value) => Ok(Expr::Imaginary(value)),
+            Token::Ident(name) if name == "pi" => Ok(Expr::Pi),
+            Token::Ident(name) if name == "i" => Ok(Expr::Imaginary(1.0)),
+            Token::Ident(name) if FUNCTIONS.contains(&name.as_str()) => {
+                self.expect('(')?;
+                let argument = self.expression(params)?;
+                self.expect(')')?;
+                Ok(Expr::Call(name, Box::new(argument)))
+            }
+            Token::Param(name) if params.contains(&name) => Ok(Expr::Param(name)),
+            Token::Param(name) => Err(self.error(format!("unknown parameter %{name}"))),
+            Token::Symbol('(') => {
+                let expr = self.expression(params)?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            _ => Err(self.error("expected an expression")),
+        }
+    }
+}
+
+/// A gate defined in the program with the `DEFGATE` statement.
+#[derive(Debug, Clone)]
+struct GateDefinition {
+    params: Vec<String>,
+    n_qubits: usize,
+    body: DefinitionBody,
+}
+
+#[derive(Debug, Clone)]
+enum DefinitionBody {
+    /// The rows of the matrix of the gate.
+    Matrix(Vec<Vec<Expr>>),
+    /// The image of each basis state under the gate.
+    Permutation(Vec<usize>),
+}
+
+impl GateDefinition {
+    fn gate<T: Float + 'static>(&self, params: &[T]) -> Result<QuGate<T>, QubError> {
+        let dim = 1 << self.n_qubits;
+        let matrix = match &self.body {
+            DefinitionBody::Matrix(rows) => {
+                let values = self
+                    .params
+                    .iter()
+                    .map(String::as_str)
+                    .zip(params.iter().copied())
+                    .collect();
+                let entries = rows
+                    .iter()
+                    .map(|row| row.iter().map(|expr| expr.eval(&values)).collect())
+                    .collect::<Result<Vec<Vec<_>>, _>>()?;
+                Array2::from_shape_fn((dim, dim), |(row, column)| entries[row][column])
+            }
+            DefinitionBody::Permutation(images) => {
+                Array2::from_shape_fn((dim, dim), |(row, column)| {
+                    if images[column] == row {
+                        Complex::new(T::one(), T::zero())
+                    } else {
+                        Complex::new(T::zero(), T::zero())
+                    }
+                })
+            }
+        };
+        QuGate::try_new(matrix, T::epsilon().sqrt())
+    }
+}
+
+/// A forward jump waiting for its label.
+struct Jump {
+    label: String,
+    /// The classical bit the jump reads.
+    clbit: usize,
+    /// The value of the bit for which the operations up to the label run.
+    value: bool,
+    /// Whether an operation up to the label has measured into the bit,
+    /// after which the bit no longer holds the value the jump read.
+    overwritten: bool,
+}
+
+struct Parser<T: Float> {
+    /// The offset and size of each classical memory region.
+    memory: HashMap<String, (usize, usize)>,
+    n_qubits: usize,
+    n_clbits: usize,
+    gates: HashMap<String, GateDefinition>,
+    labels: Vec<String>,
+    /// The pending forward jumps, which condition the following operations.
+    jumps: Vec<Jump>,
+    /// The operations of the circuit, with the line they appear on.
+    operations: Vec<(usize, Operation<T>)>,
+}
+
+impl<T: Float + 'static> Parser<T> {
+    fn program(&mut self, source: &str) -> Result<(), QubError> {
+        let mut lines = source
+            .lines()
+            .enumerate()
+            .map(|(index, text)| (index + 1, text.split('#').next().unwrap_or("")))
+            .peekable();
+
+        while let Some((number, text)) = lines.next() {
+            if text.trim_start().starts_with("PRAGMA") {
+                continue;
+            }
+            let mut line = Line {
+                tokens: tokenize(text, number)?,
+                position: 0,
+                number,
+            };
+            if line.at_end() {
+                continue;
+            }
+
+            let keyword = line.ident()?;
+            match keyword.as_str() {
+                "DECLARE" => self.declare(&mut line)?,
+                "DEFGATE" => {
+                    let mut rows = Vec::new();
+                    while let Some(&(number, text)) = lines.peek() {
+                        if !text.trim().is_empty() && !text.starts_with(char::is_whitespace) {
+                            break;
+                        }
+                        lines.next();
+                        if !text.trim().is_empty() {
+                            rows.push(Line {
+                                tokens: tokenize(text, number)?,
+                                position: 0,
+                                number,
+                            });
+                        }
+                    }
+                    self.define(&mut line, rows)?;
+                }
+                "MEASURE" => {
+                    let qubit = line.int()?;
+                    if line.at_end() {
+                        return Err(line.error("measurements must name a memory reference"));
+                    }
+                    let clbit = self.memory_reference(&mut line)?;
+                    line.end()?;
+                    self.record(number, Operation::Measure { qubit, clbit })?;
+                    for jump in self.jumps.iter_mut().filter(|jump| jump.clbit == clbit) {
+                        jump.overwritten = true;
+                    }
+                }
+                "LABEL" => {
+                    let label = line.label()?;
+                    line.end()?;
+                    self.jumps.retain(|jump| jump.label != label);
+                    self.labels.push(label);
+                }
+                "JUMP-WHEN" | "JUMP-UNLESS" => {
+                    let label = line.label()?;
+                    let clbit = self.memory_reference(&mut line)?;
+                    line.end()?;
+                    if self.labels.contains(&label) {
+                        return Err(line.error("backward jumps are not supported"));
+                    }
+                    // The operations up to the label only run when the jump is not taken.
+                    self.jumps.push(Jump {
+                        label,
+                        clbit,
+                        value: keyword == "JUMP-UNLESS",
+                        overwritten: false,
+                    });
+                }
+                "NOP" => line.end()?,
+                "HALT" => break,
+                "JUMP" | "RESET" | "WAIT" | "DEFCIRCUIT" | "INCLUDE" => {
+                    return Err(line.error(format!("{keyword} is not supported")));
+                }
+                _ => {
+                    line.position -= 1;
+                    self.application(&mut line)?;
+                }
+            }
+        }
+
+        if let Some(jump) = self.jumps.first() {
+            return Err(QubError::Parse {
+                line: source.lines().count(),
+                message: format!("label @{} is never defined", jump.label),
+            });
+        }
+        Ok(())
+    }
+
+    /// Record an operation, conditioned on the pending forward jumps.
+    /// A circuit checks the condition of each operation when it runs,
+    /// so this fails if a jump reads a bit measured into since the jump.
+    fn record(&mut self, line: usize, operation: Operation<T>) -> Result<(), QubError> {
+        if let Some(jump) = self.jumps.iter().find(|jump| jump.overwritten) {
+            return Err(QubError::Parse {
+                line,
+                message: format!(
+                    "operations after a measurement into the bit read by the jump to @{} \
+                     are not supported",
+                    jump.label
+                ),
+            });
+        }
+
+        let operation = if self.jumps.is_empty() {
+            operation
+        } else {
+            Operation::Conditional {
+                clbits: self.jumps.iter().map(|jump| jump.clbit).collect(),
+                value: self
+                    .jumps
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, jump)| jump.value)
+                    .map(|(bit, _)| 1 << bit)
+                    .sum(),
+                operation: Box::new(operation),
+            }
+        };
+        self.operations.push((line, operation));
+        Ok(())
+    }
+
+    fn declare(&mut self, line: &mut Line) -> Result<(), QubError> {
+        let name = line.ident()?;
+        if line.ident()? != "BIT" {
+            return Err(line.error("only BIT memory is supported"));
+        }
+        let size = if line.eat('[') {
+            let size = line.int()?;
+            line.expect(']')?;
+            size
+        } else {
+            1
+        };
+        line.end()?;
+
+        if self.memory.contains_key(&name) {
+            return Err(line.error(format!("memory {name} is already declared")));
+        }
+        self.memory.insert(name, (self.n_clbits, size));
+        self.n_clbits += size;
+        Ok(())
+    }
+
+    fn memory_reference(&self, line: &mut Line) -> Result<usize, QubError> {
+        let name = line.ident()?;
+        let (offset, size) = *self
+            .memory
+            .get(&name)
+            .ok_or_else(|| line.error(format!("unknown memory {name}")))?;
+
+        let index = if line.eat('[') {
+            let index = line.int()?;
+            line.expect(']')?;
+            index
+        } else {
+            0
+        };
+        if index >= size {
+            return Err(line.error(format!(
+                "index {index} out of range for memory {name} of size {size}"
+            )));
+        }
+        Ok(offset + index)
+    }
+
+    fn define(&mut self, line: &mut Line, mut rows: Vec<Line>) -> Result<(), QubError> {
+        let name = line.ident()?;
+        if self.gates.contains_key(&name) || STANDARD_GATES.iter().any(|gate| gate.0 == name) {
+            return Err(line.error(format!("gate {name} is already defined")));
+        }
+
+        let mut params = Vec::new();
+        if line.eat('(') {
+            loop {
+                match line.next()? {
+                    Token::Param(param) => params.push(param),
+                    _ => return Err(line.error("expected a parameter")),
+                }
+                if !line.eat(',') {
+                    break;
+                }
+            }
+            line.expect(')')?;
+        }
+        let permutation = if line.tokens.get(line.position) == Some(&Token::Ident("AS".to_string()))
+        {
+            line.position += 1;
+            match line.ident()?.as_str() {
+                "MATRIX" => false,
+                "PERMUTATION" => true,
+                kind => return Err(line.error(format!("{kind} gates are not supported"))),
+            }
+        } else {
+            false
+        };
+        line.expect(':')?;
+        line.end()?;
+
+        let body = if permutation {
+            let row = rows
+                .first_mut()
+                .ok_or_else(|| line.error("expected a permutation"))?;
+            let mut images = vec![row.int()?];
+            while row.eat(',') {
+                images.push(row.int()?);
+            }
+            row.end()?;
+
+            let mut sorted = images.clone();
+            sorted.sort_unstable();
+            if rows.len() != 1 || !sorted.into_iter().eq(0..images.len()) {
+                return Err(line.error(format!("gate {name} is not a valid permutation")));
+            }
+            DefinitionBody::Permutation(images)
+        } else {
+            let rows = rows
+                .iter_mut()
+                .map(|row| {
+                    let exprs = row.expressions(&params)?;
+                    row.end()?;
+                    Ok(exprs)
+                })
+                .collect::<Result<Vec<_>, QubError>>()?;
+            if rows.iter().any(|row| row.len() != rows.len()) {
+                return Err(line.error(format!("matrix of gate {name} is not square")));
+            }
+            DefinitionBody::Matrix(rows)
+        };
+
+        let dim = match &body {
+            DefinitionBody::Matrix(rows) => rows.len(),
+            DefinitionBody::Permutation(images) => images.len(),
+        };
+        if dim < 2 || !dim.is_power_of_two() {
+            return Err(line.error(format!(
+                "gate {name} must have a power of two size of at least 2"
+            )));
+        }
+
+        self.gates.insert(
+            name,
+            GateDefinition {
+                params,
+                n_qubits: dim.trailing_zeros() as usize,
+                body,
+            },
+        );
+        Ok(())
+    }
+
+    fn application(&mut self, line: &mut Line) -> Result<(), QubError> {
+        let mut n_modifier_controls = 0;
+        let mut daggers = 0;
+        let name = loop {
+            let word = line.ident()?;
+            match word.as_str() {
+                "CONTROLLED" => n_modifier_controls += 1,
+                "DAGGER" => daggers += 1,
+                "FORKED" => return Err(line.error("FORKED gates are not supported")),
+                _ => break word,
+            }
+        };
+
+        let mut params: Vec<T> = Vec::new();
+        if line.eat('(') {
+            for expr in line.expressions(&[])? {
+                let value: Complex<T> = expr.eval(&HashMap::new())?;
+                if value.im.abs() > T::epsilon().sqrt() {
+                    return Err(line.error("gate parameters must be real"));
+                }
+                params.push(value.re);
+            }
+            line.expect(')')?;
+        }
+        let mut qubits = Vec::new();
+        while !line.at_end() {
+            qubits.push(line.int()?);
+        }
+
+        let (mut circuit_name, n_params, n_qubits, n_controls) =
+            if let Some(definition) = self.gates.get(&name) {
+                let lowercase = name.to_lowercase();
+                (lowercase, definition.params.len(), definition.n_qubits, 0)
+            } else if let Some(&(_, n_params, n_qubits, circuit_name, n_controls)) =
+                STANDARD_GATES.iter().find(|gate| gate.0 == name)
+            {
+                (circuit_name.to_string(), n_params, n_qubits, n_controls)
+            } else {
+                return Err(line.error(format!("unknown gate {name}")));
+            };
+        if params.len() != n_params || qubits.len() != n_modifier_controls + n_qubits {
+            return Err(line.error(format!(
+                "gate {name} takes {n_params} parameters and {} qubits, found {} and {}",
+                n_modifier_controls + n_qubits,
+                params.len(),
+                qubits.len()
+            )));
+        }
+
+        let mut gate = match self.gates.get(&name) {
+            Some(definition) => {
+                let gate = definition
+                    .gate(&params)
+                    .map_err(|error| line.error(format!("gate {name}: {error}")))?;
+                // Prefer the exact built-in gate when a definition reproduces one.
+                builtin_gate(&circuit_name, &params)
+                    .filter(|named| named.approx_eq(&gate, T::epsilon().sqrt()))
+                    .unwrap_or(gate)
+            }
+            None => builtin_gate(&circuit_name, &params).expect("standard gates are built-in"),
+        };
+        for _ in 0..daggers {
+            gate = gate.dagger();
+            if let Some((dagger, dagger_params)) = dagger_name(&circuit_name, &params) {
+                circuit_name = dagger.to_string();
+                params = dagger_params;
+            }
+        }
+
+        let n_controls = n_modifier_controls + n_controls;
+        self.n_qubits = self
+            .n_qubits
+            .max(qubits.iter().max().map_or(0, |max| max + 1));
+        self.record(
+            line.number,
+            Operation::Gate {
+                name: circuit_name,
+                params,
+                gate,
+                controls: qubits[..n_controls].to_vec(),
+                targets: qubits[n_controls..].to_vec(),
+            },
+        )
+    }
+}
+
+/// Format a complex number as a Quil expression, e.g. `0.5-0.5i`.
+fn format_complex<T: Float>(value: Complex<T>) -> String {
+    let (re, im) = (format_number(value.re), format_number(value.im.abs()));
+    match (value.re.is_zero(), value.im.is_zero(), value.im < T::zero()) {
+        (_, true, _) => re,
+        (true, false, false) => format!("{im}i"),
+        (true, false, true) => format!("-{im}i"),
+        (false, false, false) => format!("{re}+{im}i"),
+        (false, false, true) => format!("{re}-{im}i"),
+    }
+}
+
+/// Collects the gate definitions and labels an exported program needs.
+struct Exporter<T: Float> {
+    definitions: Vec<String>,
+    /// The matrices of the custom gates defined so far, by name.
+    matrices: Vec<(String, Array2<Complex<T>>)>,
+    n_labels: usize,
+}
+
+impl<T: Float + 'static> Exporter<T> {
+    /// Add a definition if it is not already part of the program.
+    fn require(&mut self, definition: &str) {
+        if !self
+            .definitions
+            .iter()
+            .any(|existing| existing == definition)
+        {
+            self.definitions.push(definition.to_string());
+        }
+    }
+
+    /// Get the Quil name of a built-in gate, preceded by any `DAGGER` modifier,
+    /// and the number of its controls that the name already includes.
+    fn builtin_name(&mut self, name: &str, n_controls: usize) -> (String, usize) {
+        if let Some(gate) = STANDARD_GATES
+            .iter()
+            .filter(|gate| gate.3 == name && gate.4 <= n_controls)
+            .max_by_key(|gate| gate.4)
+        {
+            return (gate.0.to_string(), gate.4);
+        }
+
+        let (quil_name, definition) = match name {
+            "sdg" => ("DAGGER S", None),
+            "tdg" => ("DAGGER T", None),
+            "sx" => ("SX", Some(SX_DEFINITION)),
+            "sxdg" => ("DAGGER SX", Some(SX_DEFINITION)),
+            "u3" => ("U3", Some(U3_DEFINITION)),
+            "rxx" => ("RXX", Some(RXX_DEFINITION)),
+            _ => ("RZZ", Some(RZZ_DEFINITION)),
+        };
+        if let Some(definition) = definition {
+            self.require(definition);
+        }
+        (quil_name.to_string(), 0)
+    }
+
+    /// Get the name of the definition of a custom gate, adding it if needed.
+    /// The name is derived from the name of the operation, with a suffix
+    /// when the same name is used for different matrices.
+    fn custom_name(&mut self, name: &str, matrix: &Array2<Complex<T>>) -> String {
+        let mut base: String = name
+            .to_uppercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        if !base.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            base.insert_str(0, "G_");
+        }
+
+        let tolerance = T::epsilon().sqrt();
+        for suffix in 1.. {
+            let candidate = match suffix {
+                1 => base.clone(),
+                _ => format!("{base}_{suffix}"),
+            };
+            if RESERVED_NAMES.contains(&candidate.as_str())
+                || STANDARD_GATES.iter().any(|gate| gate.0 == candidate)
+            {
+                continue;
+            }
+
+            match self
+                .matrices
+                .iter()
+                .find(|(existing, _)| *existing == candidate)
+            {
+                Some((_, existing)) if compare_matrices(existing, matrix, tolerance) => {
+                    return candidate;
+                }
+                Some(_) => continue,
+                None => {
+                    let rows: Vec<String> = matrix
+                        .rows()
+                        .into_iter()
+                        .map(|row| {
+                            let entries: Vec<String> =
+                                row.iter().map(|&entry| format_complex(entry)).collect();
+                            format!("    {}", entries.join(", "))
+                        })
+                        .collect();
+                    self.definitions
+                        .push(format!("DEFGATE {candidate}:\n{}", rows.join("\n")));
+                    self.matrices.push((candidate.clone(), matrix.clone()));
+                    return candidate;
+                }
+            }
+        }
+        unreachable!("some suffix is always free")
+    }
+
+    /// Get the Quil instructions of an operation.
+    fn instructions(&mut self, operation: &Operation<T>) -> Vec<String> {
+        match operation {
+            Operation::Gate {
+                name,
+                params,
+                gate,
+                controls,
+                targets,
+            } => {
+                let builtin = builtin_gate(name, params)
+                    .is_some_and(|named| named.approx_eq(gate, T::epsilon().sqrt()));
+                let (quil_name, params, n_named_controls) = if builtin {
+                    let (quil_name, n_named_controls) = self.builtin_name(name, controls.len());
+                    (quil_name, params.as_slice(), n_named_controls)
+                } else {
+                    (self.custom_name(name, gate.matrix()), &[][..], 0)
+                };
+
+                let mut instruction = "CONTROLLED ".repeat(controls.len() - n_named_controls);
+                instruction.push_str(&quil_name);
+                if !params.is_empty() {
+                    let params: Vec<String> =
+                        params.iter().map(|&param| format_number(param)).collect();
+                    instruction.push_str(&format!("({})", params.join(", ")));
+                }
+                for qubit in controls.iter().chain(targets) {
+                    instruction.push_str(&format!(" {qubit}"));
+                }
+                vec![instruction]
+            }
+            Operation::Measure { qubit, clbit } => vec![format!("MEASURE {qubit} ro[{clbit}]")],
+            Operation::Barrier(_) => Vec::new(),
+            Operation::Conditional {
+                clbits,
+                value,
+                operation,
+            } => {
+                let label = format!("@skip_{}", self.n_labels);
+                self.n_labels += 1;
+
+                let jumps = clbits.iter().enumerate().map(|(bit, clbit)| {
+                    if bit < usize::BITS as usize && (value >> bit) & 1 == 1 {
+                        format!("JUMP-UNLESS {label} ro[{clbit}]")
+                    } else {
+                        format!("JUMP-WHEN {label} ro[{clbit}]")
+                    }
+                });
+                jumps
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .chain(self.instructions(operation))
+                    .chain(iter::once(format!("LABEL {label}")))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn compare_matrices<T: Float>(
+    a: &Array2<Complex<T>>,
+    b: &Array2<Complex<T>>,
+    tolerance: T,
+) -> bool {
+    a.dim() == b.dim() && a.iter().zip(b).all(|(a, b)| (a - b).norm() <= tolerance)
+}
+
+impl<T: Float + 'static> QuCircuit<T> {
+    /// Parse a Quil program into a circuit.
+    /// Qubits are numbered as in the program, `BIT` memory is laid out
+    /// in declaration order, and forward jumps conditioned on a single bit
+    /// become conditional operations, so a jump region may not go on
+    /// past a measurement into the bit its jump reads.
+    /// Gates defined with `DEFGATE` are recorded under their lowercase name.
+    pub fn from_quil(source: &str) -> Result<Self, QubError> {
+        let mut parser = Parser {
+            memory: HashMap::new(),
+            n_qubits: 0,
+            n_clbits: 0,
+            gates: HashMap::new(),
+            labels: Vec::new(),
+            jumps: Vec::new(),
+            operations: Vec::new(),
+        };
+        parser.program(source)?;
+
+        let n_qubits = parser
+            .operations
+            .iter()
+            .filter_map(|(_, operation)| match operation {
+                Operation::Measure { qubit, .. } => Some(qubit + 1),
+                _ => None,
+            })
+            .fold(parser.n_qubits, usize::max);
+        let mut circuit = Self::with_clbits(n_qubits, parser.n_clbits);
+        for (line, operation) in parser.operations {
+            circuit
+                .try_push(operation)
+                .map_err(|error| QubError::Parse {
+                    line,
+                    message: error.to_string(),
+                })?;
+        }
+        Ok(circuit)
+    }
+
+    /// Serialise the circuit to a Quil program with a `ro` memory region.
+    /// Built-in gates missing from Quil are added as parametric definitions,
+    /// any other gate is added as a definition with its matrix,
+    /// and conditional operations are guarded by forward jumps.
+    /// Barriers have no Quil equivalent and are left out.
+    pub fn to_quil(&self) -> String {
+        let mut exporter = Exporter {
+            definitions: Vec::new(),
+            matrices: Vec::new(),
+            n_labels: 0,
+        };
+        let body: Vec<String> = self
+            .operations()
+            .iter()
+            .flat_map(|operation| exporter.instructions(operation))
+            .collect();
+
+        let mut program = String::new();
+        for line in (self.n_clbits() > 0)
+            .then(|| format!("DECLARE ro BIT[{}]", self.n_clbits()))
+            .into_iter()
+            .chain(exporter.definitions)
+            .chain(body)
+        {
+            program.push_str(&line);
+            program.push('\n');
+        }
+        program
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quregister::QuRegister;
+
+    #[test]
+    fn parse() {
+        let circuit = QuCircuit::<f64>::from_quil(
+            "# Prepare a GHZ state.
+DECLARE ro BIT[3]
+DEFGATE SQRTX:
+    0.5+0.5i, 0.5-0.5i
+    0.5-0.5i, 0.5+0.5i
+
+DEFGATE CRY(%theta):
+    1, 0, 0, 0
+    0, 1, 0, 0
+    0, 0, cos(%theta/2), -sin(%theta/2)
+    0, 0, sin(%theta/2), cos(%theta/2)
+DEFGATE TOFFOLI AS PERMUTATION:
+    0, 1, 2, 3, 4, 5, 7, 6
+H 0
+CNOT 0 1
+CONTROLLED X 1 2
+CRY(pi) 2 1
+DAGGER CRY(pi) 2 1
+SQRTX 0
+DAGGER SQRTX 0
+TOFFOLI 0 1 2
+TOFFOLI 0 1 2
+DAGGER S 1
+S 1
+PRAGMA INITIAL_REWIRING \"NAIVE\"
+MEASURE 0 ro[0]
+MEASURE 1 ro[1]
+MEASURE 2 ro[2]
+",
+        )
+        .unwrap();
+
+        assert_eq!(circuit.n_qubits(), 3);
+        assert_eq!(circuit.operations().len(), 14);
+        assert!(matches!(
+            &circuit.operations()[9],
+            Operation::Gate { name, .. } if name == "sdg"
+        ));
+        let (a, b) = (circuit.run(), circuit.run());
+        assert!(a.clbits().iter().all(|&bit| bit == a.clbits()[0]));
+        assert!(b.clbits().iter().all(|&bit| bit == b.clbits()[0]));
+    }
+
+    #[test]
+    fn standard_gates() {
+        let circuit = QuCircuit::<f64>::from_quil(
+            "X 1
+ISWAP 0 1
+PSWAP(pi) 1 2
+CPHASE10(pi/2) 0 2
+CPHASE00(0.3) 1 2
+DAGGER CPHASE01(0.5) 1 0",
+        )
+        .unwrap();
+
+        let amplitude = circuit.run().state().amplitude(0b100);
+        assert!((amplitude + Complex::cis(0.3 - 0.5)).norm() < 1e-12);
+
+        let program = circuit.to_quil();
+        assert!(!program.contains("DEFGATE"));
+        assert!(program.starts_with("X 1\nISWAP 0 1\nPSWAP(3.14"));
+        assert!(program.ends_with("\nCPHASE00(0.3) 1 2\nCPHASE01(-0.5) 1 0\n"));
+        assert_eq!(QuCircuit::from_quil(&program).unwrap(), circuit);
+    }
+
+    #[test]
+    fn conditional() {
+        let circuit = QuCircuit::<f64>::from_quil(
+            "DECLARE flags BIT[2]
+X 0
+MEASURE 0 flags[1]
+JUMP-UNLESS @skip flags[1]
+JUMP-WHEN @skip flags[0]
+X 1
+LABEL @skip
+X 2
+HALT
+X 2",
+        )
+        .unwrap();
+
+        assert_eq!(circuit.n_qubits(), 3);
+        assert_eq!(circuit.run().state(), &QuRegister::basis(3, 0b111));
+        assert!(matches!(
+            &circuit.operations()[2],
+            Operation::Conditional { clbits, value: 0b01, .. } if clbits == &[1, 0]
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let custom = QuGate::new(QuGate::<f64>::hadamard().matrix().dot(QuGate::t().matrix()));
+        let circuit = QuCircuit::<f64>::with_clbits(3, 2)
+            .h(0)
+            .cx(0, 1)
+            .ccx(0, 1, 2)
+            .cswap(2, 0, 1)
+            .tdg(2)
+            .u3(0.1, 0.2, 0.3, 1)
+            .controlled_gate("rzz", vec![0.4], QuGate::rzz(0.4), &[2], &[0, 1])
+            .gate("custom", custom.clone(), &[0])
+            .gate("custom", QuGate::hadamard(), &[1])
+            .measure_into(2, 1)
+            .conditional(&[1], 1, Operation::Measure { qubit: 0, clbit: 0 })
+            .barrier();
+
+        let program = circuit.to_quil();
+        assert!(program.starts_with("DECLARE ro BIT[2]\nDEFGATE U3(%theta, %phi, %lambda):"));
+        assert!(program.contains("\nCCNOT 0 1 2\nCSWAP 2 0 1\nDAGGER T 2\nU3(0.1, 0.2, 0.3) 1\n"));
+        assert!(program.contains("\nCONTROLLED RZZ(0.4) 2 0 1\nCUSTOM 0\nCUSTOM_2 1\n"));
+        assert!(program.ends_with(
+            "MEASURE 2 ro[1]\nJUMP-UNLESS @skip_0 ro[1]\nMEASURE 0 ro[0]\nLABEL @skip_0\n"
+        ));
+
+        let parsed = QuCircuit::from_quil(&program).unwrap();
+        assert_eq!(parsed.operations().len(), circuit.operations().len() - 1);
+        for (a, b) in parsed.operations().iter().zip(circuit.operations()) {
+            match (a, b) {
+                (
+                    Operation::Gate {
+                        name: a_name,
+                        gate: a_gate,
+                        controls: a_controls,
+                        targets: a_targets,
+                        ..
+                    },
+                    Operation::Gate {
+                        name: b_name,
+                        gate: b_gate,
+                        controls: b_controls,
+                        targets: b_targets,
+                        ..
+                    },
+                ) => {
+                    assert!(a_name.starts_with(b_name.as_str()));
+                    assert!(a_gate.approx_eq(b_gate, 1e-12));
+                    assert_eq!((a_controls, a_targets), (b_controls, b_targets));
+                }
+                (a, b) => assert_eq!(a, b),
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let parse = QuCircuit::<f64>::from_quil;
+
+        assert_eq!(
+            parse("H 0\nFOO 1"),
+            Err(QubError::Parse {
+                line: 2,
+                message: "unknown gate FOO".to_string()
+            })
+        );
+        assert_eq!(
+            parse("DECLARE ro BIT\nLABEL @start\nJUMP-WHEN @start ro"),
+            Err(QubError::Parse {
+                line: 3,
+                message: "backward jumps are not supported".to_string()
+            })
+        );
+        assert!(parse("DEFGATE BAD:\n    1, 1\n    0, 1\nBAD 0").is_err());
+        assert!(parse("DEFGATE ODD:\n    1, 0, 0\n    0, 1, 0\n    0, 0, 1").is_err());
+        assert!(parse("CNOT 0 0").is_err());
+        assert!(parse("RX 0").is_err());
+        assert!(parse("MEASURE 0").is_err());
+        assert_eq!(
+            parse("DECLARE ro BIT[1]\nJUMP-WHEN @end ro[0]\nX 0\nMEASURE 0 ro[0]\nX 1\nLABEL @end"),
+            Err(QubError::Parse {
+                line: 5,
+                message: "operations after a measurement into the bit read by the jump to @end \
+                          are not supported"
+                    .to_string()
+            })
+        );
+        assert!(parse("DECLARE ro BIT\nJUMP-WHEN @end ro\nMEASURE 0 ro\nLABEL @end\nX 0").is_ok());
+    }
+}
@@ -0,0 +1,572 @@
+use ndarray::prelude::*;
+use num::{complex::Complex, Float};
+use rand::prelude::*;
+
+use crate::{
+    compare, counts::Counts, error::QubError, pauli::PauliString, qubit::Qubit, qugate::QuGate,
+};
+
+/// A quantum register is a collection of n qubits.
+/// Unlike a single qubit, a register can hold entangled states.
+/// The state of a register is described by a complex vector of size 2^n,
+/// where qubit 0 is the most significant bit of the basis state index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuRegister<T: Float> {
+    /// The state of the register, represented as a complex vector of size 2^n.
+    pub(crate) state: Array1<Complex<T>>,
+}
+
+impl<T: Float> QuRegister<T> {
+    /// Create a new register from the given amplitudes.
+    ///
+    /// # Panics
+    /// Panics if the number of amplitudes is not a non-zero power of two.
+    pub fn new(state: Array1<Complex<T>>) -> Self {
+        Self::try_new(state).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Create a new register from the given amplitudes,
+    /// failing if the number of amplitudes is not a non-zero power of two.
+    pub fn try_new(state: Array1<Complex<T>>) -> Result<Self, QubError> {
+        if !state.len().is_power_of_two() {
+            return Err(QubError::InvalidLength { len: state.len() });
+        }
+
+        Ok(Self { state })
+    }
+
+    /// Create a new register of n qubits in the |0...0⟩ state.
+    pub fn zero(n_qubits: usize) -> Self {
+        Self::basis(n_qubits, 0)
+    }
+
+    /// Create a new register of n qubits in the given computational basis state.
+    /// The index is read as a bitstring with qubit 0 as the most significant bit,
+    /// e.g. `basis(3, 0b011)` is the |011⟩ state.
+    ///
+    /// # Panics
+    /// Panics if the index is out of range for n qubits.
+    pub fn basis(n_qubits: usize, index: usize) -> Self {
+        Self::try_basis(n_qubits, index).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Create a new register of n qubits in the given computational basis state,
+    /// failing if the index is out of range for n qubits
+    /// or if n qubits have too many basis states to index.
+    pub fn try_basis(n_qubits: usize, index: usize) -> Result<Self, QubError> {
+        let len = u32::try_from(n_qubits)
+            .ok()
+            .and_then(|shift| 1usize.checked_shl(shift))
+            .ok_or(QubError::TooManyQubits { n_qubits })?;
+        if index >= len {
+            return Err(QubError::IndexOutOfRange { index, len });
+        }
+
+        let mut state = Array1::from_elem(len, Complex::new(T::zero(), T::zero()));
+        state[index] = Complex::new(T::one(), T::zero());
+
+        Ok(Self { state })
+    }
+
+    /// Get the current state of the register.
+    pub fn get_state(&self) -> &Array1<Complex<T>> {
+        &self.state
+    }
+
+    /// Get the number of qubits in the register.
+    pub fn n_qubits(&self) -> usize {
+        self.state.len().trailing_zeros() as usize
+    }
+
+    /// Get the current amplitude of the given basis state.
+    pub fn amplitude(&self, index: usize) -> Complex<T> {
+        self.state[index]
+    }
+
+    /// Get the inner product ⟨self|other⟩ of this register with another.
+    pub fn inner(&self, other: &Self) -> Complex<T> {
+        self.state
+            .iter()
+            .zip(&other.state)
+            .fold(Complex::new(T::zero(), T::zero()), |acc, (a, b)| {
+                acc + a.conj() * b
+            })
+    }
+
+    /// Get the probabilities of the register being in each basis state.
+    pub fn probabilities(&self) -> Array1<T> {
+        self.state.mapv(|x| x.norm_sqr())
+    }
+
+    /// Get the probability of the register being in the given basis state.
+    pub fn probability(&self, index: usize) -> T {
+        self.state[index].norm_sqr()
+    }
+
+    /// Validate the register state.
+    pub fn validate(&self) -> bool {
+        self.probabilities().sum() == T::one()
+    }
+
+    /// Validate the register state, allowing its norm to differ from one
+    /// by at most the given tolerance to absorb floating point errors.
+    pub fn validate_with(&self, tolerance: T) -> bool {
+        (self.probabilities().sum() - T::one()).abs() <= tolerance
+    }
+
+    /// Check whether this register equals another within the given tolerance
+    /// on each amplitude.
+    pub fn approx_eq(&self, other: &Self, tolerance: T) -> bool {
+        compare::approx_eq(&self.state, &other.state, tolerance)
+    }
+
+    /// Check whether this register equals another within the given tolerance
+    /// up to a global phase, which has no physical effect,
+    /// e.g. -|0⟩ and |0⟩ are equal up to global phase.
+    pub fn eq_up_to_global_phase(&self, other: &Self, tolerance: T) -> bool {
+        compare::eq_up_to_global_phase(&self.state, &other.state, tolerance)
+    }
+
+    /// Measure every qubit of the register in the computational basis.
+    /// Collapse the register to one of its basis states.
+    pub fn measure(&self) -> Self {
+        self.measure_with(&mut thread_rng())
+    }
+
+    /// Measure every qubit of the register in the computational basis,
+    /// drawing randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if the register has zero norm.
+    pub fn measure_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Self {
+        self.try_measure_with(rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Measure every qubit of the register in the computational basis,
+    /// drawing randomness from the given random number generator,
+    /// failing if the register has zero norm.
+    pub fn try_measure_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<Self, QubError> {
+        let outcome = sample_index(&self.probabilities().to_vec(), rng)?;
+        Ok(Self::basis(self.n_qubits(), outcome))
+    }
+
+    /// Measure a single qubit of the register in the computational basis.
+    /// Return the observed bit and the register with that qubit collapsed
+    /// and the remaining amplitudes renormalised.
+    ///
+    /// # Panics
+    /// Panics if the qubit is out of range.
+    pub fn measure_qubit(&self, qubit: usize) -> (bool, Self) {
+        self.measure_qubit_with(qubit, &mut thread_rng())
+    }
+
+    /// Measure a single qubit of the register in the computational basis,
+    /// drawing randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if the qubit is out of range.
+    pub fn measure_qubit_with<R: Rng + ?Sized>(&self, qubit: usize, rng: &mut R) -> (bool, Self) {
+        let measurement = self.measure_qubits_with(&[qubit], rng);
+        (measurement.outcomes[0], measurement.state)
+    }
+
+    /// Measure the given qubits of the register in the computational basis.
+    /// Return the observed bits, in the order of the given qubits,
+    /// their joint probability and the register with the measured qubits collapsed
+    /// and the remaining amplitudes renormalised.
+    ///
+    /// # Panics
+    /// Panics if a qubit is out of range or repeated.
+    pub fn measure_qubits(&self, qubits: &[usize]) -> RegisterMeasurement<T> {
+        self.measure_qubits_with(qubits, &mut thread_rng())
+    }
+
+    /// Measure the given qubits of the register in the computational basis,
+    /// drawing randomness from the given random number generator.
+    ///
+    /// # Panics
+    /// Panics if a qubit is out of range or repeated, or if the register has zero norm.
+    pub fn measure_qubits_with<R: Rng + ?Sized>(
+        &self,
+        qubits: &[usize],
+        rng: &mut R,
+    ) -> RegisterMeasurement<T> {
+        self.try_measure_qubits_with(qubits, rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Measure the given qubits of the register in the computational basis,
+    /// drawing randomness from the given random number generator,
+    /// failing if a qubit is out of range or repeated, or if the register has zero norm.
+    pub fn try_measure_qubits_with<R: Rng + ?Sized>(
+        &self,
+        qubits: &[usize],
+        rng: &mut R,
+    ) -> Result<RegisterMeasurement<T>, QubError> {
+        let masks = qubit_masks(qubits, self.n_qubits())?;
+
+        // Outcome of the measured qubits for a basis state, as a bitstring
+        // with the first measured qubit as the most significant bit.
+        let outcome_of = |index: usize| {
+            masks
+                .iter()
+                .fold(0, |acc, mask| (acc << 1) | usize::from(index & mask != 0))
+        };
+
+        let mut outcome_probabilities = vec![T::zero(); 1 << qubits.len()];
+        for (index, x) in self.state.indexed_iter() {
+            outcome_probabilities[outcome_of(index)] =
+                outcome_probabilities[outcome_of(index)] + x.norm_sqr();
+        }
+
+        let outcome = sample_index(&outcome_probabilities, rng)?;
+        let probability = outcome_probabilities[outcome];
+        let norm = probability.sqrt();
+        let state = Array1::from_shape_fn(self.state.len(), |index| {
+            if outcome_of(index) == outcome {
+                self.state[index] / norm
+            } else {
+                Complex::new(T::zero(), T::zero())
+            }
+        });
+
+        Ok(RegisterMeasurement {
+            outcomes: (0..qubits.len())
+                .map(|j| outcome & (1 << (qubits.len() - 1 - j)) != 0)
+                .collect(),
+            probability,
+            state: Self { state },
+        })
+    }
+
+    /// Sample the given number of measurement shots of every qubit
+    /// in the computational basis, without re-simulating the register for each shot.
+    ///
+    /// # Panics
+    /// Panics if the register has zero norm.
+    pub fn sample<R: Rng + ?Sized>(&self, shots: usize, rng: &mut R) -> Counts {
+        self.try_sample(shots, rng)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Sample the given number of measurement shots of every qubit
+    /// in the computational basis, without re-simulating the register for each shot,
+    /// failing if the register has zero norm.
+    pub fn try_sample<R: Rng + ?Sized>(
+        &self,
+        shots: usize,
+        rng: &mut R,
+    ) -> Result<Counts, QubError> {
+        Counts::sample(self.n_qubits(), self.probabilities(), shots, rng)
+    }
+
+    /// Combine this register with another into the joint state |self⟩ ⊗ |other⟩.
+    /// The qubits of `self` come first in the resulting register.
+    pub fn tensor(&self, other: &Self) -> Self {
+        let state = self
+            .state
+            .iter()
+            .flat_map(|&a| other.state.iter().map(move |&b| a * b))
+            .collect();
+
+        Self { state }
+    }
+}
+
+impl<T: Float + 'static> QuRegister<T> {
+    /// Get the expectation value ⟨ψ|O|ψ⟩ of a Hermitian observable
+    /// acting on every qubit of the register.
+    ///
+    /// # Panics
+    /// Panics if the observable does not act on as many qubits as the register holds.
+    pub fn expectation(&self, observable: &QuGate<T>) -> T {
+        self.inner(&observable.apply_register(self)).re
+    }
+
+    /// Get the expectation value of a Pauli string observable such as `"XZIY"`,
+    /// with one symbol per qubit starting from qubit 0
+    /// and an optional `+` or `-` sign prefix.
+    ///
+    /// # Panics
+    /// Panics if the string contains symbols other than I, X, Y and Z,
+    /// has an imaginary phase prefix such as `i`,
+    /// or if its length does not match the number of qubits.
+    pub fn expectation_pauli(&self, paulis: &str) -> T {
+        self.try_expectation_pauli(paulis)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Get the expectation value of a Pauli string observable such as `"-XZIY"`,
+    /// failing if the string contains symbols other than I, X, Y and Z,
+    /// has an imaginary phase prefix such as `i`,
+    /// or if its length does not match the number of qubits.
+    pub fn try_expectation_pauli(&self, paulis: &str) -> Result<T, QubError> {
+        let paulis: PauliString = paulis.parse()?;
+        if !paulis.is_hermitian() {
+            return Err(QubError::NotHermitian);
+        }
+        if paulis.n_qubits() != self.n_qubits() {
+            return Err(QubError::DimensionMismatch {
+                expected: paulis.n_qubits(),
+                found: self.n_qubits(),
+            });
+        }
+
+        Ok(paulis.expectation(self).re)
+    }
+}
+
+/// Get the basis state mask of each of the given qubits in an n-qubit register,
+/// failing if a qubit is out of range or repeated.
+pub(crate) fn qubit_masks(qubits: &[usize], n_qubits: usize) -> Result<Vec<usize>, QubError> {
+    let mut full_mask = 0;
+    qubits
+        .iter()
+        .map(|&qubit| {
+            if qubit >= n_qubits {
+                return Err(QubError::QubitOutOfRange { qubit, n_qubits });
+            }
+            let mask = 1 << (n_qubits - 1 - qubit);
+            if full_mask & mask != 0 {
+                return Err(QubError::DuplicateQubit { qubit });
+            }
+            full_mask |= mask;
+            Ok(mask)
+        })
+        .collect()
+}
+
+/// Pick an index at random with the given, possibly unnormalised, probabilities,
+/// failing if they are all zero.
+pub(crate) fn sample_index<T: Float, R: Rng + ?Sized>(
+    probabilities: &[T],
+    rng: &mut R,
+) -> Result<usize, QubError> {
+    let total = probabilities.iter().fold(T::zero(), |acc, &p| acc + p);
+    if total <= T::zero() {
+        return Err(QubError::ZeroNorm);
+    }
+
+    let r = T::from(rng.gen::<f64>()).ok_or(QubError::NumericConversion)? * total;
+    let mut cumulative = T::zero();
+    for (index, &p) in probabilities.iter().enumerate() {
+        cumulative = cumulative + p;
+        if r < cumulative {
+            return Ok(index);
+        }
+    }
+
+    // Absorb rounding errors in the cumulative sum.
+    Ok(probabilities.len() - 1)
+}
+
+/// The result of measuring some qubits of a register.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterMeasurement<T: Float> {
+    outcomes: Vec<bool>,
+    probability: T,
+    state: QuRegister<T>,
+}
+
+impl<T: Float> RegisterMeasurement<T> {
+    /// Get the observed bits, in the order the qubits were measured.
+    pub fn outcomes(&self) -> &[bool] {
+        &self.outcomes
+    }
+
+    /// Get the joint probability of the observed outcomes before the measurement.
+    pub fn probability(&self) -> T {
+        self.probability
+    }
+
+    /// Get the register after the measurement.
+    pub fn state(&self) -> &QuRegister<T> {
+        &self.state
+    }
+
+    /// Take the register after the measurement.
+    pub fn into_state(self) -> QuRegister<T> {
+        self.state
+    }
+}
+
+impl<T: Float> Default for QuRegister<T> {
+    fn default() -> Self {
+        Self::zero(1)
+    }
+}
+
+impl<T: Float> From<Qubit<T>> for QuRegister<T> {
+    fn from(qubit: Qubit<T>) -> Self {
+        Self { state: qubit.state }
+    }
+}
+
+#[cfg(feature = "approx")]
+impl<T: Float + approx::AbsDiffEq<Epsilon = T>> approx::AbsDiffEq for QuRegister<T> {
+    type Epsilon = T;
+
+    fn default_epsilon() -> T {
+        T::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: T) -> bool {
+        self.approx_eq(other, epsilon)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl<T: Float + approx::RelativeEq<Epsilon = T>> approx::RelativeEq for QuRegister<T> {
+    fn default_max_relative() -> T {
+        T::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: T, max_relative: T) -> bool {
+        compare::relative_eq(&self.state, &other.state, epsilon, max_relative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero() {
+        let register = QuRegister::<f64>::zero(3);
+        assert_eq!(register.n_qubits(), 3);
+        assert_eq!(register.probability(0), 1.0);
+        assert_eq!(register, register.measure());
+    }
+
+    #[test]
+    fn basis() {
+        let register = QuRegister::<f64>::basis(3, 0b101);
+        assert!(register.validate());
+        assert!(register.validate_with(0.0));
+        assert_eq!(register.probabilities()[0b101], 1.0);
+        assert_eq!(register, register.measure());
+    }
+
+    #[test]
+    fn from_qubit() {
+        let register = QuRegister::from(Qubit::<f64>::one());
+        assert_eq!(register.n_qubits(), 1);
+        assert_eq!(register, QuRegister::basis(1, 1));
+    }
+
+    #[test]
+    fn measure_qubit() {
+        let register = QuRegister::<f64>::basis(3, 0b010);
+        assert_eq!(register.measure_qubit(1), (true, register.clone()));
+        assert_eq!(register.measure_qubit(2), (false, register.clone()));
+    }
+
+    #[test]
+    fn measure_qubits() {
+        // (|000⟩ + |011⟩ + |101⟩ + |110⟩) / 2, where qubit 0 is always
+        // the parity of qubits 1 and 2.
+        let half = Complex::new(0.5, 0.0);
+        let zero = Complex::new(0.0, 0.0);
+        let register =
+            QuRegister::<f64>::new(array![half, zero, zero, half, zero, half, half, zero]);
+
+        let measurement = register.measure_qubits(&[2, 1]);
+        let (q2, q1) = (measurement.outcomes()[0], measurement.outcomes()[1]);
+        let q0 = q1 ^ q2;
+        let index = usize::from(q0) << 2 | usize::from(q1) << 1 | usize::from(q2);
+
+        assert_eq!(measurement.probability(), 0.25);
+        assert_eq!(measurement.into_state(), QuRegister::basis(3, index));
+    }
+
+    #[test]
+    fn sample() {
+        let counts = QuRegister::<f64>::basis(3, 0b011).sample(50, &mut thread_rng());
+        assert_eq!(counts.get("011"), 50);
+        assert_eq!(counts.shots(), 50);
+    }
+
+    #[test]
+    fn errors() {
+        let mut rng = thread_rng();
+        let register = QuRegister::<f64>::zero(2);
+
+        assert_eq!(
+            QuRegister::<f64>::try_new(Array1::zeros(3)),
+            Err(QubError::InvalidLength { len: 3 })
+        );
+        assert_eq!(
+            QuRegister::<f64>::try_basis(2, 4),
+            Err(QubError::IndexOutOfRange { index: 4, len: 4 })
+        );
+        assert_eq!(
+            QuRegister::<f64>::try_basis(usize::BITS as usize, 0),
+            Err(QubError::TooManyQubits {
+                n_qubits: usize::BITS as usize
+            })
+        );
+        assert_eq!(
+            register.try_measure_qubits_with(&[2], &mut rng),
+            Err(QubError::QubitOutOfRange {
+                qubit: 2,
+                n_qubits: 2
+            })
+        );
+        assert_eq!(
+            register.try_measure_qubits_with(&[1, 1], &mut rng),
+            Err(QubError::DuplicateQubit { qubit: 1 })
+        );
+        assert_eq!(
+            QuRegister::<f64>::new(Array1::zeros(2)).try_measure_with(&mut rng),
+            Err(QubError::ZeroNorm)
+        );
+        assert_eq!(
+            QuRegister::<f64>::new(Array1::zeros(2)).try_sample(10, &mut rng),
+            Err(QubError::ZeroNorm)
+        );
+    }
+
+    #[test]
+    fn eq_up_to_global_phase() {
+        let register = QuRegister::<f64>::basis(2, 0b11);
+        let rotated = QuGate::rz(1.0).apply_to(&register, 0);
+
+        assert!(!rotated.approx_eq(&register, 1e-12));
+        assert!(rotated.eq_up_to_global_phase(&register, 1e-12));
+    }
+
+    #[test]
+    fn expectation() {
+        let bell = QuGate::cnot()
+            .apply_register(&QuGate::hadamard().apply_to(&QuRegister::<f64>::zero(2), 0));
+
+        assert!((bell.expectation_pauli("ZZ") - 1.0).abs() < 1e-12);
+        assert!((bell.expectation_pauli("XX") - 1.0).abs() < 1e-12);
+        assert!((bell.expectation_pauli("YY") + 1.0).abs() < 1e-12);
+        assert!(bell.expectation_pauli("ZI").abs() < 1e-12);
+        assert!((bell.expectation_pauli("-ZZ") + 1.0).abs() < 1e-12);
+        assert!((bell.expectation(&QuGate::swap()) - 1.0).abs() < 1e-12);
+        assert_eq!(
+            bell.try_expectation_pauli("XQ"),
+            Err(QubError::InvalidPauli { symbol: 'Q' })
+        );
+        assert_eq!(
+            bell.try_expectation_pauli("iZZ"),
+            Err(QubError::NotHermitian)
+        );
+        assert_eq!(
+            bell.try_expectation_pauli("XYZ"),
+            Err(QubError::DimensionMismatch {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn tensor() {
+        let register = QuRegister::<f64>::basis(2, 0b10).tensor(&QuRegister::basis(1, 1));
+        assert_eq!(register, QuRegister::basis(3, 0b101));
+    }
+}
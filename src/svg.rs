@@ -0,0 +1,381 @@
+use num::Float;
+
+use crate::{
+    diagram::Diagram,
+    qucircuit::{Operation, QuCircuit},
+};
+
+/// The distance between two neighbouring wires.
+const WIRE_GAP: f64 = 50.0;
+/// The space around the drawing.
+const MARGIN: f64 = 20.0;
+/// The space taken by the wire names.
+const NAME_WIDTH: f64 = 40.0;
+/// The space between two layers of operations.
+const LAYER_GAP: f64 = 16.0;
+/// The height of a box on a single wire.
+const BOX_HEIGHT: f64 = 36.0;
+/// The width of a box with a short label.
+const BOX_WIDTH: f64 = 36.0;
+/// The width of a layer without any box.
+const BARE_WIDTH: f64 = 24.0;
+/// The approximate width of a character of a gate name and of its parameters.
+const CHAR_WIDTH: [f64; 2] = [9.0, 6.0];
+/// The distance between the two lines of a classical wire.
+const DOUBLE_GAP: f64 = 3.0;
+/// The radius of control dots and condition marks.
+const DOT_RADIUS: f64 = 5.0;
+
+/// The elements of an SVG image being drawn.
+struct Svg {
+    elements: Vec<String>,
+}
+
+impl Svg {
+    fn line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64), style: &str) {
+        self.elements.push(format!(
+            r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="black"{style}/>"#
+        ));
+    }
+
+    /// Draw a pair of lines, as used for classical wires.
+    fn double_line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) {
+        let length = (x2 - x1).hypot(y2 - y1);
+        let dx = (y1 - y2) / length * DOUBLE_GAP / 2.0;
+        let dy = (x2 - x1) / length * DOUBLE_GAP / 2.0;
+        for side in [-1.0, 1.0] {
+            let offset = (side * dx, side * dy);
+            self.line(
+                (x1 + offset.0, y1 + offset.1),
+                (x2 + offset.0, y2 + offset.1),
+                "",
+            );
+        }
+    }
+
+    fn rect(&mut self, (x, y): (f64, f64), (width, height): (f64, f64), style: &str) {
+        self.elements.push(format!(
+            r#"<rect x="{x}" y="{y}" width="{width}" height="{height}"{style}/>"#
+        ));
+    }
+
+    fn circle(&mut self, (cx, cy): (f64, f64), fill: &str) {
+        self.elements.push(format!(
+            r#"<circle cx="{cx}" cy="{cy}" r="{DOT_RADIUS}" fill="{fill}" stroke="black"/>"#
+        ));
+    }
+
+    fn text(&mut self, (x, y): (f64, f64), size: f64, anchor: &str, text: &str) {
+        self.elements.push(format!(
+            r#"<text x="{x}" y="{y}" font-size="{size}" text-anchor="{anchor}" dominant-baseline="central">{}</text>"#,
+            escape(text)
+        ));
+    }
+
+    fn path(&mut self, path: &str, fill: &str) {
+        self.elements.push(format!(
+            r#"<path d="{path}" fill="{fill}" stroke="black"/>"#
+        ));
+    }
+}
+
+/// Escape the characters with a meaning in XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Get the vertical position of a wire.
+fn wire_y(wire: usize) -> f64 {
+    MARGIN + WIRE_GAP * (wire as f64 + 0.5)
+}
+
+/// Get the width of a box holding a gate name and its parameters.
+fn box_width(name: &str, params: &[String]) -> f64 {
+    let name_width = name.chars().count() as f64 * CHAR_WIDTH[0] + 16.0;
+    let params_width = params.join(", ").chars().count() as f64 * CHAR_WIDTH[1] + 12.0;
+    BOX_WIDTH.max(name_width).max(params_width)
+}
+
+impl<T: Float + 'static> QuCircuit<T> {
+    /// Render the circuit as a standalone SVG image.
+    /// Operations are laid out in the same layers as the text diagram,
+    /// with gates drawn as labelled boxes joined to their control dots
+    /// and measurements as meters joined to the classical bit they write.
+    pub fn to_svg(&self) -> String {
+        let diagram = Diagram::new(self);
+        let layers = diagram.layers();
+        let n_qubits = self.n_qubits();
+
+        let widths: Vec<f64> = layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .filter_map(|operation| diagram.label_parts(operation))
+                    .map(|(name, params)| box_width(&name, &params))
+                    .fold(BARE_WIDTH, f64::max)
+            })
+            .collect();
+        let start = MARGIN + NAME_WIDTH;
+        let end = start + widths.iter().map(|width| width + LAYER_GAP).sum::<f64>() + LAYER_GAP;
+        let width = end + MARGIN;
+        let height = 2.0 * MARGIN + WIRE_GAP * diagram.n_wires() as f64;
+
+        let mut svg = Svg {
+            elements: Vec::new(),
+        };
+        svg.rect((0.0, 0.0), (width, height), r#" fill="white""#);
+        for wire in 0..diagram.n_wires() {
+            let y = wire_y(wire);
+            let name = match wire < n_qubits {
+                true => format!("q{wire}"),
+                false => format!("c{}", wire - n_qubits),
+            };
+            svg.text((start - 8.0, y), 14.0, "end", &name);
+            match wire < n_qubits {
+                true => svg.line((start, y), (end, y), ""),
+                false => svg.double_line((start, y), (end, y)),
+            }
+        }
+
+        let mut x = start + LAYER_GAP;
+        for (layer, layer_width) in layers.iter().zip(&widths) {
+            for operation in layer {
+                draw(
+                    &mut svg,
+                    &diagram,
+                    operation,
+                    x + layer_width / 2.0,
+                    n_qubits,
+                );
+            }
+            x += layer_width + LAYER_GAP;
+        }
+
+        let mut image = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\">\n"
+        );
+        for element in svg.elements {
+            image.push_str("  ");
+            image.push_str(&element);
+            image.push('\n');
+        }
+        image.push_str("</svg>\n");
+        image
+    }
+}
+
+/// Draw an operation centred on the given horizontal position.
+fn draw<T: Float + 'static>(
+    svg: &mut Svg,
+    diagram: &Diagram<'_, T>,
+    operation: &Operation<T>,
+    x: f64,
+    n_qubits: usize,
+) {
+    match operation {
+        Operation::Gate {
+            controls, targets, ..
+        } => {
+            let (first, last) = diagram.span(operation);
+            if first < last {
+                svg.line((x, wire_y(first)), (x, wire_y(last)), "");
+            }
+
+            // A gate without targets, like a controlled phase, is boxed on its controls.
+            let boxed = if targets.is_empty() {
+                controls
+            } else {
+                targets
+            };
+            match diagram.label_parts(operation) {
+                Some((name, params)) => {
+                    if let (Some(&top), Some(&bottom)) = (boxed.iter().min(), boxed.iter().max()) {
+                        gate_box(svg, x, (top, bottom), &name, &params);
+                    }
+                    if boxed.len() > 1 {
+                        let left = x - box_width(&name, &params) / 2.0;
+                        for (ordinal, &qubit) in boxed.iter().enumerate() {
+                            svg.text(
+                                (left + 4.0, wire_y(qubit)),
+                                9.0,
+                                "start",
+                                &ordinal.to_string(),
+                            );
+                        }
+                    }
+                }
+                None => {
+                    for &target in targets {
+                        let y = wire_y(target);
+                        svg.line((x - 6.0, y - 6.0), (x + 6.0, y + 6.0), "");
+                        svg.line((x - 6.0, y + 6.0), (x + 6.0, y - 6.0), "");
+                    }
+                }
+            }
+            if !targets.is_empty() {
+                for &control in controls {
+                    svg.circle((x, wire_y(control)), "black");
+                }
+            }
+        }
+        Operation::Measure { qubit, clbit } => {
+            let (y, clbit_y) = (wire_y(*qubit), wire_y(n_qubits + clbit));
+            svg.double_line((x, y), (x, clbit_y - 8.0));
+            svg.path(
+                &format!(
+                    "M {} {} L {} {} L {x} {clbit_y} Z",
+                    x - 5.0,
+                    clbit_y - 8.0,
+                    x + 5.0,
+                    clbit_y - 8.0
+                ),
+                "black",
+            );
+            gate_box(svg, x, (*qubit, *qubit), "", &[]);
+            svg.path(
+                &format!(
+                    "M {} {} A 12 12 0 0 1 {} {}",
+                    x - 12.0,
+                    y + 8.0,
+                    x + 12.0,
+                    y + 8.0
+                ),
+                "none",
+            );
+            svg.line((x, y + 8.0), (x + 9.0, y - 10.0), "");
+        }
+        Operation::Barrier(qubits) => {
+            for &qubit in qubits {
+                let y = wire_y(qubit);
+                let (top, bottom) = (y - WIRE_GAP / 2.0, y + WIRE_GAP / 2.0);
+                svg.rect(
+                    (x - 8.0, top),
+                    (16.0, WIRE_GAP),
+                    r#" fill="lightgray" fill-opacity="0.6""#,
+                );
+                svg.line((x, top), (x, bottom), r#" stroke-dasharray="4 4""#);
+            }
+        }
+        Operation::Conditional {
+            clbits,
+            value,
+            operation,
+        } => {
+            let (_, last) = diagram.span(operation);
+            if let Some(&lowest) = clbits.iter().max() {
+                if n_qubits + lowest > last {
+                    svg.double_line((x, wire_y(last)), (x, wire_y(n_qubits + lowest)));
+                }
+            }
+            draw(svg, diagram, operation, x, n_qubits);
+
+            for (bit, &clbit) in clbits.iter().enumerate() {
+                let set = bit < usize::BITS as usize && (value >> bit) & 1 == 1;
+                let fill = if set { "black" } else { "white" };
+                svg.circle((x, wire_y(n_qubits + clbit)), fill);
+            }
+        }
+    }
+}
+
+/// Draw a box across the wires from `top` to `bottom`, labelled with a gate
+/// name over its parameters.
+fn gate_box(svg: &mut Svg, x: f64, (top, bottom): (usize, usize), name: &str, params: &[String]) {
+    let width = box_width(name, params);
+    let (y, height) = (
+        wire_y(top) - BOX_HEIGHT / 2.0,
+        wire_y(bottom) - wire_y(top) + BOX_HEIGHT,
+    );
+    svg.rect(
+        (x - width / 2.0, y),
+        (width, height),
+        r#" rx="3" fill="white" stroke="black""#,
+    );
+
+    let center = y + height / 2.0;
+    if name.is_empty() {
+        return;
+    }
+    if params.is_empty() {
+        svg.text((x, center), 14.0, "middle", name);
+    } else {
+        svg.text((x, center - 7.0), 14.0, "middle", name);
+        svg.text((x, center + 9.0), 10.0, "middle", &params.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bell_pair() {
+        let svg = QuCircuit::<f64>::new(2)
+            .h(0)
+            .cx(0, 1)
+            .measure_all()
+            .to_svg();
+
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.contains(r#"<svg xmlns="http://www.w3.org/2000/svg""#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches(">H</text>").count(), 1);
+        assert_eq!(svg.matches(">X</text>").count(), 1);
+        assert_eq!(svg.matches(r#"fill="black" stroke="black"/>"#).count(), 3);
+        assert_eq!(svg.matches(" A 12 12 ").count(), 2);
+        for name in ["q0", "q1", "c0", "c1"] {
+            assert!(svg.contains(&format!(">{name}</text>")));
+        }
+    }
+
+    #[test]
+    fn parameters_and_conditions() {
+        let circuit = QuCircuit::<f64>::with_clbits(3, 2)
+            .u3(std::f64::consts::PI, 0.25, 0.0, 0)
+            .swap(1, 2)
+            .barrier()
+            .conditional(
+                &[0, 1],
+                2,
+                Operation::Gate {
+                    name: "rzz".to_string(),
+                    params: vec![1.0],
+                    gate: crate::qugate::QuGate::rzz(1.0),
+                    controls: Vec::new(),
+                    targets: vec![2, 0],
+                },
+            );
+        let svg = circuit.to_svg();
+
+        assert!(svg.contains(">U3</text>"));
+        assert!(svg.contains(">π, 0.25, 0</text>"));
+        assert!(svg.contains(">RZZ</text>"));
+        assert!(svg.contains(">1</text>"));
+        assert_eq!(svg.matches("stroke-dasharray").count(), 3);
+        assert_eq!(svg.matches(r#"r="5" fill="white""#).count(), 1);
+        assert_eq!(svg.matches(r#"r="5" fill="black""#).count(), 1);
+    }
+
+    #[test]
+    fn no_targets() {
+        let phase = crate::qugate::QuGate::new(ndarray::array![[num::Complex::new(0.0, 1.0)]]);
+        let svg = QuCircuit::<f64>::new(2)
+            .gate("gphase", phase.clone(), &[])
+            .controlled_gate("i", Vec::new(), phase, &[0, 1], &[])
+            .to_svg();
+
+        assert_eq!(svg.matches(">I</text>").count(), 1);
+        assert!(!svg.contains("<circle"));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}